@@ -11,6 +11,16 @@ pub struct PagingMeta {
     pub prev_page: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiError {
+    pub detail: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiErrors {
+    pub errors: Vec<ApiError>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Meta {
     pub total: u64,
@@ -53,6 +63,12 @@ pub struct Crates {
     pub meta: PagingMeta,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CratesByIds {
+    pub found: HashMap<String, Crate>,
+    pub missing: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VersionLinks {
     pub authors: String,
@@ -79,6 +95,17 @@ pub struct Version {
     pub published_by: Option<User>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Versions {
+    pub versions: Vec<Version>,
+    pub meta: PagingMeta,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VersionResponse {
+    pub version: Version,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Category {
     pub category: String,
@@ -137,6 +164,11 @@ pub struct VersionDownloads {
     pub version: u64,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VersionDownloadsResponse {
+    pub version_downloads: Vec<VersionDownloads>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ExtraDownloads {
     pub date: NaiveDate,
@@ -165,6 +197,48 @@ pub struct User {
     pub url: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UserResponse {
+    pub user: User,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Team {
+    pub avatar: Option<String>,
+    pub id: u64,
+    pub kind: Option<String>,
+    pub login: String,
+    pub name: Option<String>,
+    pub url: Option<String>,
+}
+
+impl Team {
+    /// Returns the GitHub organization, parsed from a login such as
+    /// `github:rust-lang:core`.
+    pub fn github_org(&self) -> Option<&str> {
+        self.github_parts().map(|(org, _)| org)
+    }
+
+    /// Returns the GitHub team within the organization, parsed from a login
+    /// such as `github:rust-lang:core`.
+    pub fn github_team(&self) -> Option<&str> {
+        self.github_parts().map(|(_, team)| team)
+    }
+
+    fn github_parts(&self) -> Option<(&str, &str)> {
+        let mut parts = self.login.splitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("github"), Some(org), Some(team)) => Some((org, team)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TeamResponse {
+    pub team: Team,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AuthorsMeta {
     pub names: Vec<String>,
@@ -182,11 +256,25 @@ pub struct Authors {
     pub users: Vec<User>,
 }
 
+impl From<AuthorsResponse> for Authors {
+    fn from(response: AuthorsResponse) -> Self {
+        Self {
+            names: response.meta.names,
+            users: response.users,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Owners {
     pub users: Vec<User>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TeamOwners {
+    pub teams: Vec<Team>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Dependency {
     pub crate_id: String,
@@ -205,3 +293,95 @@ pub struct Dependency {
 pub struct Dependencies {
     pub dependencies: Vec<Dependency>,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReverseDependencies {
+    pub dependencies: Vec<Dependency>,
+    pub versions: Vec<Version>,
+    pub meta: Meta,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReverseDependency {
+    pub dependency: Dependency,
+    pub version: Option<Version>,
+}
+
+impl ReverseDependencies {
+    /// Pairs every dependency record with the dependent crate version it
+    /// belongs to.
+    pub fn into_pairs(self) -> Vec<ReverseDependency> {
+        let versions = self.versions;
+        self.dependencies
+            .into_iter()
+            .map(|dependency| ReverseDependency {
+                version: versions
+                    .iter()
+                    .find(|v| v.id == dependency.version_id)
+                    .cloned(),
+                dependency,
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Me {
+    pub user: User,
+    pub owned_crates: Vec<OwnedCrate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OwnedCrate {
+    pub id: u64,
+    pub name: String,
+    pub email_notifications: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Updates {
+    pub versions: Vec<Version>,
+    pub meta: UpdatesMeta,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdatesMeta {
+    pub more: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FollowingResponse {
+    pub following: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team(login: &str) -> Team {
+        Team {
+            avatar: None,
+            id: 1,
+            kind: Some("team".to_string()),
+            login: login.to_string(),
+            name: None,
+            url: None,
+        }
+    }
+
+    #[test]
+    fn parses_github_team_login() {
+        let team = team("github:rust-lang:core");
+        assert_eq!(team.github_org(), Some("rust-lang"));
+        assert_eq!(team.github_team(), Some("core"));
+    }
+
+    #[test]
+    fn ignores_other_logins() {
+        for login in &["gitlab:rust-lang:core", "github:rust-lang", "rust-lang"] {
+            let team = team(login);
+            assert_eq!(team.github_org(), None);
+            assert_eq!(team.github_team(), None);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Api tokens for authenticated requests.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Result};
+
+/// Environment variable checked for a token before the credentials file.
+const TOKEN_ENV_VAR: &str = "CARGO_REGISTRY_TOKEN";
+
+/// Placeholder printed in place of secret values.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Registry api token, sent with every request as the `Authorization`
+/// header.
+///
+/// The token value is never printed, both `Debug` and `Display` show a
+/// redacted placeholder instead.
+///
+/// ```rust,no_run
+/// # use consecrates::{Client, Token};
+/// let client = Client::builder("my_app (github.com/me/me_app)")
+///     .token(Token::from_cargo().expect("no api token configured"))
+///     .build()
+///     .unwrap();
+/// let me = client.get_me().expect("failed getting account");
+/// println!("logged in as {}", me.user.login);
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Token(String);
+
+impl Token {
+    /// Creates a token out of its raw value.
+    pub fn new(token: &str) -> Self {
+        Self(token.trim().to_string())
+    }
+
+    /// Reads the `crates.io` token the same way cargo does, first from the
+    /// `CARGO_REGISTRY_TOKEN` environment variable, then from the
+    /// `[registry]` table of the `credentials.toml` file in cargo home.
+    ///
+    /// Cargo home is taken from `CARGO_HOME`, defaulting to `~/.cargo`. The
+    /// legacy `credentials` file without extension is read as well. Returns
+    /// `Error::InvalidConfig` if no token could be found.
+    pub fn from_cargo() -> Result<Self> {
+        if let Ok(token) = env::var(TOKEN_ENV_VAR) {
+            if !token.trim().is_empty() {
+                return Ok(Self::new(&token));
+            }
+        }
+
+        let cargo_home = cargo_home().ok_or_else(|| {
+            Error::InvalidConfig("couldn't locate the cargo home directory".to_string())
+        })?;
+        if let Some(token) = read_credentials(&cargo_home)? {
+            return Ok(Self::new(&token));
+        }
+        Err(Error::InvalidConfig(format!(
+            "no api token found in {} or {}",
+            TOKEN_ENV_VAR,
+            cargo_home.join("credentials.toml").display()
+        )))
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Token({})", REDACTED)
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", REDACTED)
+    }
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    if let Some(cargo_home) = env::var_os("CARGO_HOME") {
+        return Some(PathBuf::from(cargo_home));
+    }
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".cargo"))
+}
+
+/// Reads the `crates.io` token from the credentials files in the given cargo
+/// home directory, preferring `credentials.toml`.
+fn read_credentials(cargo_home: &Path) -> Result<Option<String>> {
+    for file_name in &["credentials.toml", "credentials"] {
+        let contents = match fs::read_to_string(cargo_home.join(file_name)) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(token) = parse_credentials(&contents) {
+            return Ok(Some(token));
+        }
+    }
+    Ok(None)
+}
+
+/// Extracts `token` from the `[registry]` table of a cargo credentials file.
+///
+/// Only understands the subset of toml cargo itself writes, i.e. a table
+/// header followed by `key = "value"` lines, as well as the dotted
+/// `registry.token = "value"` form.
+fn parse_credentials(contents: &str) -> Option<String> {
+    let mut table = "";
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            table = line.trim_matches(|c| c == '[' || c == ']').trim();
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+        let is_token = match table {
+            "registry" => key == "token",
+            "" => key == "registry.token",
+            _ => false,
+        };
+        if is_token {
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.split_once('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.split_once('\'')))
+                .map(|(value, _)| value)?;
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_registry_table() {
+        let contents = "# written by cargo\n\
+                        [registries.other]\n\
+                        token = \"wrong\"\n\
+                        \n\
+                        [registry]\n\
+                        token = \"right\"\n";
+        assert_eq!(parse_credentials(contents).as_deref(), Some("right"));
+    }
+
+    #[test]
+    fn parses_dotted_key_and_single_quotes() {
+        assert_eq!(
+            parse_credentials("registry.token = 'dotted'").as_deref(),
+            Some("dotted")
+        );
+        assert_eq!(
+            parse_credentials("[registry]\ntoken=\"tight\" # comment").as_deref(),
+            Some("tight")
+        );
+    }
+
+    #[test]
+    fn ignores_other_tokens() {
+        assert_eq!(parse_credentials(""), None);
+        assert_eq!(parse_credentials("token = \"top-level\""), None);
+        assert_eq!(parse_credentials("[registries.x]\ntoken = \"x\""), None);
+        assert_eq!(parse_credentials("[registry]\ntoken = unquoted"), None);
+    }
+
+    #[test]
+    fn reads_credentials_files() {
+        let cargo_home =
+            std::env::temp_dir().join(format!("consecrates-auth-{}", std::process::id()));
+        fs::create_dir_all(&cargo_home).unwrap();
+        assert_eq!(read_credentials(&cargo_home).unwrap(), None);
+
+        fs::write(
+            cargo_home.join("credentials"),
+            "[registry]\ntoken = \"legacy\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            read_credentials(&cargo_home).unwrap().as_deref(),
+            Some("legacy")
+        );
+        fs::write(
+            cargo_home.join("credentials.toml"),
+            "[registry]\ntoken = \"current\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            read_credentials(&cargo_home).unwrap().as_deref(),
+            Some("current")
+        );
+        fs::remove_dir_all(&cargo_home).unwrap();
+    }
+}
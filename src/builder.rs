@@ -0,0 +1,286 @@
+//! Client configuration.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::Token;
+use crate::cache::Cache;
+use crate::rate_limit::{self, RateLimiter};
+use crate::retry::RetryPolicy;
+use crate::transport::{HttpReqTransport, Timeouts, Transport};
+use crate::{Client, Error, Result, BASE_URL};
+
+/// Builder for configuring a `Client`.
+///
+/// All of the configuration is checked when calling `build`, invalid
+/// settings or combinations of settings are reported with
+/// `Error::InvalidConfig`.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use consecrates::{ClientBuilder, RetryPolicy};
+/// let client = ClientBuilder::new("my_app (github.com/me/me_app)")
+///     .connect_timeout(Some(Duration::from_secs(5)))
+///     .read_timeout(Some(Duration::from_secs(20)))
+///     .retry_policy(RetryPolicy::new(5))
+///     .cache(Duration::from_secs(300))
+///     .build()
+///     .expect("invalid client config");
+/// ```
+pub struct ClientBuilder {
+    base_url: String,
+    user_agent: String,
+    headers: Vec<(String, String)>,
+    token: Option<Token>,
+    timeouts: Timeouts,
+    acquire_timeout: Option<Duration>,
+    rate_limiter: Arc<RateLimiter>,
+    retry_policy: RetryPolicy,
+    cache_ttl: Option<Duration>,
+    transport: Box<dyn Transport>,
+}
+
+impl ClientBuilder {
+    /// Creates a new builder with the given user agent string.
+    ///
+    /// See `Client::new` for user agent requirements.
+    pub fn new(user_agent: &str) -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            user_agent: user_agent.to_string(),
+            headers: Vec::new(),
+            token: None,
+            timeouts: Timeouts::default(),
+            acquire_timeout: None,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            retry_policy: RetryPolicy::default(),
+            cache_ttl: None,
+            transport: Box::new(HttpReqTransport),
+        }
+    }
+
+    /// Sets the base url of the registry api, defaults to `crates.io`.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// Adds a custom header sent along with every request.
+    ///
+    /// The `User-Agent` and `Authorization` headers can't be set this way.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the api token sent with every request, required by endpoints
+    /// describing the authenticated user, e.g. `Client::get_me`.
+    pub fn token(mut self, token: Token) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection, defaults to 10
+    /// seconds. `None` means waiting indefinitely.
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeouts.connect = timeout;
+        self
+    }
+
+    /// Sets the timeout for reading the response, defaults to 30 seconds.
+    /// `None` means waiting indefinitely.
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeouts.read = timeout;
+        self
+    }
+
+    /// Sets the timeout for sending the request, defaults to 30 seconds.
+    /// `None` means waiting indefinitely.
+    pub fn write_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeouts.write = timeout;
+        self
+    }
+
+    /// Sets the maximum time the blocking `get_*` methods wait for the rate
+    /// limiter, including waiting for other threads holding its lock, before
+    /// giving up with `Error::Timeout`. Defaults to `None`, waiting
+    /// indefinitely.
+    pub fn acquire_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.acquire_timeout = timeout;
+        self
+    }
+
+    /// Sets the rate limiter, either an owned one or one shared with other
+    /// clients through an `Arc`.
+    ///
+    /// When targeting `crates.io` the limiter can't be disabled or configured
+    /// to go faster than the policy allows, see `RateLimiter` for details.
+    pub fn rate_limiter(mut self, rate_limiter: impl Into<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = rate_limiter.into();
+        self
+    }
+
+    /// Sets the policy for retrying failed requests.
+    ///
+    /// Only applies to the blocking `get_*` methods, `try_get_*` methods
+    /// never retry.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Enables in-memory caching of successful responses for the given
+    /// amount of time.
+    ///
+    /// Requests served from the cache don't count against the rate limit.
+    pub fn cache(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the transport used for performing http requests, defaults to
+    /// `HttpReqTransport`.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Box::new(transport);
+        self
+    }
+
+    /// Validates the configuration and creates the client.
+    pub fn build(self) -> Result<Client> {
+        self.validate()?;
+        Ok(self.into_client())
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.base_url.starts_with("https://") && !self.base_url.starts_with("http://") {
+            return Err(invalid(format!(
+                "base url must start with http:// or https://, got `{}`",
+                self.base_url
+            )));
+        }
+        for (name, value) in &self.headers {
+            validate_header(name, value)?;
+        }
+        if let Some(token) = &self.token {
+            let token = token.as_str();
+            if token.is_empty() || token.bytes().any(|b| b == b'\r' || b == b'\n') {
+                return Err(invalid("api token can't be empty or contain line breaks"));
+            }
+        }
+
+        let window = self
+            .rate_limiter
+            .interval()
+            .checked_mul(self.rate_limiter.burst_size());
+        if self.rate_limiter.is_enabled() && window.is_none_or(|w| w > rate_limit::MAX_WINDOW) {
+            return Err(invalid(
+                "rate limiter interval times burst can't exceed 100 years",
+            ));
+        }
+
+        if rate_limit::targets_crates_io(&self.base_url) {
+            validate_user_agent(&self.user_agent)?;
+            if !self.rate_limiter.is_enabled() {
+                return Err(invalid(
+                    "rate limiting can't be disabled when targeting crates.io",
+                ));
+            }
+            if self.rate_limiter.interval() < rate_limit::CRATES_IO_INTERVAL
+                || self.rate_limiter.burst_size() > 1
+            {
+                return Err(invalid(
+                    "crates.io tolerates at most one request per second, without bursts",
+                ));
+            }
+        }
+
+        if self.retry_policy.max_attempts() == 0 {
+            return Err(invalid("retry policy must allow at least one attempt"));
+        }
+        let timeouts = [
+            self.timeouts.connect,
+            self.timeouts.read,
+            self.timeouts.write,
+            self.acquire_timeout,
+        ];
+        if timeouts.contains(&Some(Duration::from_secs(0))) {
+            return Err(invalid("timeouts can't be zero, use `None` to disable"));
+        }
+        if self.cache_ttl == Some(Duration::from_secs(0)) {
+            return Err(invalid("cache ttl can't be zero"));
+        }
+        Ok(())
+    }
+
+    /// Creates the client without validating the configuration.
+    ///
+    /// The `crates.io` rate limit floor still applies.
+    pub(crate) fn into_client(self) -> Client {
+        let targets_crates_io = rate_limit::targets_crates_io(&self.base_url);
+        Client {
+            base_url: self.base_url,
+            user_agent: self.user_agent,
+            headers: self.headers,
+            token: self.token,
+            rate_limiter: self.rate_limiter,
+            rate_floor: if targets_crates_io {
+                Some(rate_limit::CRATES_IO_INTERVAL)
+            } else {
+                None
+            },
+            retry_policy: self.retry_policy,
+            timeouts: self.timeouts,
+            acquire_timeout: self.acquire_timeout,
+            cache: self.cache_ttl.map(Cache::new),
+            transport: self.transport,
+        }
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::InvalidConfig(msg.into())
+}
+
+/// Checks the user agent against the format required by the `crates.io`
+/// crawler policy, e.g. `my_crawler (help@my_crawler.com)`. Other registries
+/// may have different policies, so they aren't checked.
+fn validate_user_agent(user_agent: &str) -> Result<()> {
+    let valid = match user_agent.trim().split_once(" (") {
+        Some((name, rest)) => {
+            let contact = rest.strip_suffix(')').unwrap_or("");
+            !name.trim().is_empty()
+                && !contact.trim().is_empty()
+                && (contact.contains('@') || contact.contains('.'))
+        }
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(format!(
+            "user agent `{}` doesn't follow the `name (contact)` format, \
+             e.g. `my_crawler (help@my_crawler.com)`",
+            user_agent
+        )))
+    }
+}
+
+fn validate_header(name: &str, value: &str) -> Result<()> {
+    if name.eq_ignore_ascii_case("user-agent") {
+        return Err(invalid(
+            "user agent can only be set with the builder's user agent argument",
+        ));
+    }
+    if name.eq_ignore_ascii_case("authorization") {
+        return Err(invalid(
+            "authorization can only be set with the builder's token",
+        ));
+    }
+    let valid_name = !name.is_empty() && name.bytes().all(|b| b.is_ascii_graphic() && b != b':');
+    let valid_value = !value.bytes().any(|b| b == b'\r' || b == b'\n');
+    if valid_name && valid_value {
+        Ok(())
+    } else {
+        Err(invalid(format!("invalid header `{}`", name)))
+    }
+}
@@ -0,0 +1,40 @@
+//! In-memory caching of responses.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::transport::Response;
+
+/// Keeps successful responses around for a fixed amount of time, keyed by
+/// request url.
+pub(crate) struct Cache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Response)>>,
+}
+
+impl Cache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cached response for the url if it hasn't expired yet.
+    pub fn get(&self, url: &str) -> Option<Response> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(url)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, response)| response.clone())
+    }
+
+    /// Stores the response for the url, dropping any expired entries.
+    pub fn insert(&self, url: &str, response: &Response) {
+        let mut entries = self.entries.lock().unwrap();
+        let ttl = self.ttl;
+        entries.retain(|_, (stored_at, _)| stored_at.elapsed() < ttl);
+        entries.insert(url.to_string(), (Instant::now(), response.clone()));
+    }
+}
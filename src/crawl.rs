@@ -0,0 +1,203 @@
+//! Resumable crawling of whole crate listings.
+
+use std::convert::TryFrom;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::api::Crate;
+use crate::{Client, Error, Query, Result, Sorting};
+
+/// Crawls a whole crate listing page by page, persisting progress to a
+/// checkpoint file so that an interrupted crawl can be resumed.
+///
+/// The checkpoint file stores one json line per completed page, holding the
+/// page's crates along with the cursor of the next page. On restart the
+/// crawler reads the collected crates back and resumes after the last
+/// completed page. A partially written last line, e.g. after a crash, is
+/// cut off the file and that page is fetched again.
+///
+/// The checkpoint is tied to the query it was started with, resuming with a
+/// different query leads to mixed results.
+///
+/// ```rust,no_run
+/// # use consecrates::Client;
+/// let client = Client::new("my_app (github.com/me/me_app)");
+/// let crates = client
+///     .crawler("crates.checkpoint")
+///     .run(|progress| {
+///         println!(
+///             "{} pages, {}/{} crates, {:?} left",
+///             progress.pages_done,
+///             progress.crates_done,
+///             progress.total,
+///             progress.estimated_remaining
+///         )
+///     })
+///     .expect("crawl failed");
+/// ```
+pub struct Crawler<'a> {
+    client: &'a Client,
+    query: Query,
+    checkpoint_path: PathBuf,
+}
+
+/// Progress of a crawl, reported after every completed page.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    /// Pages completed so far, including ones from previous runs
+    pub pages_done: u64,
+    /// Crates collected so far, including ones from previous runs
+    pub crates_done: u64,
+    /// Total number of crates in the listing, as reported by the server
+    pub total: u64,
+    /// Estimated time until the crawl is done, based on the pace of the
+    /// current run
+    pub estimated_remaining: Option<Duration>,
+}
+
+/// Single line of the checkpoint file.
+#[derive(Serialize, Deserialize)]
+struct CompletedPage {
+    next_page: Option<String>,
+    total: u64,
+    crates: Vec<Crate>,
+}
+
+impl<'a> Crawler<'a> {
+    pub(crate) fn new(client: &'a Client, checkpoint_path: &Path) -> Self {
+        Self {
+            client,
+            query: Query {
+                sort: Some(Sorting::Alphabetical),
+                ..Default::default()
+            },
+            checkpoint_path: checkpoint_path.to_path_buf(),
+        }
+    }
+
+    /// Sets the query describing the crawled listing. Defaults to all crates
+    /// sorted alphabetically, 100 per page.
+    pub fn query(mut self, query: Query) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Runs the crawl to completion, returning all collected crates.
+    ///
+    /// `on_progress` is called after every completed page, including once
+    /// right after loading a checkpoint that already has some pages done.
+    pub fn run(self, mut on_progress: impl FnMut(&Progress)) -> Result<Vec<Crate>> {
+        let mut crates = Vec::new();
+        let mut pages_done = 0;
+        let mut total = 0;
+        let mut next_query = Some(self.query.clone());
+        let (pages, intact_len) = read_checkpoint(&self.checkpoint_path)?;
+        for page in pages {
+            pages_done += 1;
+            total = page.total;
+            crates.extend(page.crates);
+            next_query = page.next_page.map(|cursor| Query {
+                cursor: Some(cursor),
+                ..self.query.clone()
+            });
+        }
+        if pages_done > 0 {
+            on_progress(&Progress {
+                pages_done,
+                crates_done: crates.len() as u64,
+                total,
+                estimated_remaining: None,
+            });
+        }
+
+        let mut checkpoint = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.checkpoint_path)?;
+        // drop a partially written line so that new pages start on a fresh one
+        checkpoint.set_len(intact_len)?;
+        let started = Instant::now();
+        let mut pages_this_run = 0;
+        while let Some(query) = next_query.take() {
+            let page = self.client.get_crates(query)?;
+            pages_this_run += 1;
+            pages_done += 1;
+            total = page.meta.total;
+            next_query = page.meta.next_page.clone().map(|cursor| Query {
+                cursor: Some(cursor),
+                ..self.query.clone()
+            });
+
+            let completed = CompletedPage {
+                next_page: next_query.as_ref().and_then(|q| q.cursor.clone()),
+                total,
+                crates: page.crates,
+            };
+            append_page(&mut checkpoint, &completed)?;
+            crates.extend(completed.crates);
+
+            let crates_done = crates.len() as u64;
+            on_progress(&Progress {
+                pages_done,
+                crates_done,
+                total,
+                estimated_remaining: estimate(
+                    started.elapsed() / pages_this_run,
+                    total.saturating_sub(crates_done),
+                    self.query.per_page.unwrap_or(100) as u64,
+                ),
+            });
+        }
+
+        Ok(crates)
+    }
+}
+
+/// Estimates the remaining time given average time per page, number of
+/// remaining crates and page size.
+fn estimate(per_page_time: Duration, remaining: u64, per_page: u64) -> Option<Duration> {
+    if per_page == 0 {
+        return None;
+    }
+    let pages_left = remaining.div_ceil(per_page);
+    u32::try_from(pages_left)
+        .ok()
+        .and_then(|pages| per_page_time.checked_mul(pages))
+}
+
+/// Reads completed pages from the checkpoint file, along with the length in
+/// bytes of its intact part. A trailing partially written line is discarded.
+fn read_checkpoint(path: &Path) -> Result<(Vec<CompletedPage>, u64)> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(e) => return Err(e.into()),
+    };
+    let mut pages = Vec::new();
+    let mut intact_len = 0;
+    while intact_len < contents.len() {
+        let rest = &contents[intact_len..];
+        let line_end = rest.iter().position(|b| *b == b'\n');
+        let line = &rest[..line_end.unwrap_or(rest.len())];
+        let is_last = line_end.is_none_or(|end| end + 1 == rest.len());
+        match serde_json::from_slice(line) {
+            Ok(page) if line_end.is_some() => {
+                pages.push(page);
+                intact_len += line.len() + 1;
+            }
+            Err(e) if !is_last => return Err(Error::json(e, line)),
+            _ => break,
+        }
+    }
+    Ok((pages, intact_len as u64))
+}
+
+fn append_page(file: &mut File, page: &CompletedPage) -> Result<()> {
+    let mut line = serde_json::to_vec(page).map_err(|e| Error::json(e, &[]))?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    file.sync_data()?;
+    Ok(())
+}
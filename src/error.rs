@@ -0,0 +1,158 @@
+//! Error type returned by the client.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::api::ApiError;
+
+/// Result type returned by the client.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Maximum number of characters of the response body included with decoding
+/// errors.
+const SNIPPET_LEN: usize = 200;
+
+/// Describes everything that can go wrong when talking to the registry.
+#[derive(Debug)]
+pub enum Error {
+    /// Rate limiter didn't allow performing the request yet, returned only
+    /// by the `try_get_*` family of methods
+    RateLimited,
+    /// Waiting for the rate limiter took longer than the configured timeout
+    Timeout,
+    /// Underlying transport failed to perform the request
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// Server responded with an unexpected status code
+    Status {
+        /// HTTP status code of the response
+        code: u16,
+        /// Beginning of the response body
+        snippet: String,
+        /// Delay requested by the server with the `Retry-After` header
+        retry_after: Option<Duration>,
+    },
+    /// Server responded with an error payload
+    Api {
+        /// HTTP status code of the response
+        code: u16,
+        /// Errors reported by the server
+        errors: Vec<ApiError>,
+        /// Delay requested by the server with the `Retry-After` header
+        retry_after: Option<Duration>,
+    },
+    /// Requested resource doesn't exist
+    NotFound {
+        /// Url of the request
+        url: String,
+        /// Explanation provided by the server, if any
+        detail: Option<String>,
+    },
+    /// `ReplayTransport` has no fixture recorded for the request
+    MissingFixture {
+        /// Url of the request
+        url: String,
+        /// Path at which the fixture was expected
+        path: PathBuf,
+    },
+    /// Response body couldn't be decoded
+    Json {
+        /// Underlying decoding error
+        source: serde_json::Error,
+        /// Beginning of the offending response body
+        snippet: String,
+    },
+    /// Arguments provided with the call were invalid
+    InvalidQuery(String),
+    /// Client configuration was invalid
+    InvalidConfig(String),
+    /// Local filesystem operation failed
+    Io(std::io::Error),
+}
+
+impl Error {
+    /// Creates a new transport error out of any error type.
+    ///
+    /// Useful for implementing custom transports.
+    pub fn transport<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> Self {
+        Error::Transport(error.into())
+    }
+
+    /// Returns the delay requested by the server before repeating the
+    /// request, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::Status { retry_after, .. } | Error::Api { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    pub(crate) fn json(source: serde_json::Error, body: &[u8]) -> Self {
+        Error::Json {
+            source,
+            snippet: snippet(body),
+        }
+    }
+}
+
+/// Returns the beginning of the given body as lossy utf-8 text.
+pub(crate) fn snippet(body: &[u8]) -> String {
+    String::from_utf8_lossy(body)
+        .chars()
+        .take(SNIPPET_LEN)
+        .collect()
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::RateLimited => write!(f, "would block, rate limit reached"),
+            Error::Timeout => write!(f, "timed out waiting for the rate limiter"),
+            Error::Transport(e) => write!(f, "transport failure: {}", e),
+            Error::Status { code, snippet, .. } => {
+                write!(f, "unexpected status code {}: {}", code, snippet)
+            }
+            Error::Api { code, errors, .. } => {
+                write!(f, "api error (status {}): ", code)?;
+                let details = errors
+                    .iter()
+                    .map(|e| e.detail.as_str())
+                    .collect::<Vec<&str>>();
+                write!(f, "{}", details.join("; "))
+            }
+            Error::NotFound { url, detail } => match detail {
+                Some(detail) => write!(f, "not found: {} ({})", detail, url),
+                None => write!(f, "not found: {}", url),
+            },
+            Error::MissingFixture { url, path } => write!(
+                f,
+                "no recorded fixture for url {} (expected {})",
+                url,
+                path.display()
+            ),
+            Error::Json { source, snippet } => {
+                write!(f, "failed decoding response: {}, body: {}", source, snippet)
+            }
+            Error::InvalidQuery(msg) => write!(f, "invalid query: {}", msg),
+            Error::InvalidConfig(msg) => write!(f, "invalid config: {}", msg),
+            Error::Io(e) => write!(f, "io error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(e) => Some(e.as_ref()),
+            Error::Json { source, .. } => Some(source),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -8,8 +8,10 @@
 //! include:
 //! - about 70% cut in the number of dependencies
 //! - no async
-//! - no multi-request client methods like `full_crate` or
-//!   `all_crates`
+//! - no `full_crate` or `all_crates`, listings are walked lazily with
+//!   iterators such as `Client::crates_iter` or the resumable `Crawler`
+//!   instead, the few methods performing more than one request, e.g.
+//!   `Client::get_crates_by_ids`, say so in their docs
 //! - ability to use `category` and `keyword` specifiers for querying crates
 //! - ability to convert simple string composite queries such as
 //!   `api category=web keyword=crates sort=update` into valid query objects
@@ -31,7 +33,7 @@
 //! let crates = client
 //!     .get_crates(Query {
 //!         string: Some("net".to_string()),
-//!         category: Some(Category::GameDevelopment),
+//!         categories: vec![Category::GameDevelopment],
 //!         sort: Some(Sorting::RecentUpdates),
 //!         ..Default::default()
 //!     })
@@ -44,34 +46,60 @@
 //!
 //! Please consult the
 //! [official crawler policy](https://crates.io/policies#crawlers) before using
-//! this library. Rate limiting is fixed at the lowest tolerated value. When
+//! this library. Rate limiting defaults to the lowest tolerated value and
+//! can't be relaxed when targeting `crates.io`, see `RateLimiter`. When
 //! creating a client you will need to input a proper user-agent string.
 
 #[macro_use]
 extern crate serde;
 
 pub mod api;
+mod auth;
+mod builder;
+mod cache;
+mod crawl;
+mod error;
+mod pagination;
 mod query;
+mod rate_limit;
+mod retry;
+pub mod transport;
+mod url;
 
-pub use query::{Category, Query, Sorting};
+pub use auth::Token;
+pub use builder::ClientBuilder;
+pub use crawl::{Crawler, Progress};
+pub use error::{Error, Result};
+pub use pagination::{
+    CategoriesIter, CratesIter, KeywordsIter, Page, Paginator, ReverseDependenciesIter,
+    VersionsIter,
+};
+pub use query::{Category, Query, Sorting, VersionSorting};
+pub use rate_limit::RateLimiter;
+pub use retry::RetryPolicy;
+pub use transport::{HttpReqTransport, RecordingTransport, ReplayTransport, Timeouts, Transport};
 
-use std::convert::TryFrom;
-use std::sync::Mutex;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use anyhow::{Error, Result};
-use http_req::request::Request;
-use http_req::uri::Uri;
 use serde::de::DeserializeOwned;
 
+use cache::Cache;
+use url::UrlBuilder;
+
 use api::{
-    Authors, Categories, Crate, Crates, Dependencies, Downloads, Keywords, Owners, Summary, Version,
+    ApiErrors, Authors, AuthorsResponse, Categories, CrateResponse, Crates, CratesByIds,
+    Dependencies, Downloads, FollowingResponse, Keywords, Me, Owners, ReverseDependencies, Summary,
+    Team, TeamOwners, TeamResponse, Updates, User, UserResponse, Version, VersionDownloads,
+    VersionDownloadsResponse, VersionResponse, Versions,
 };
 
 /// Base url of the API.
-const BASE_URL: &'static str = "https://crates.io/api/v1/";
-/// Rate limit of one second is the smallest value tolerated by `crates.io`.
-const RATE_LIMIT: Duration = Duration::from_secs(1);
+const BASE_URL: &str = "https://crates.io/api/v1/";
+/// Maximum number of crates returned with a single listing page.
+const MAX_PER_PAGE: usize = 100;
 
 /// API client abstraction.
 pub struct Client {
@@ -79,8 +107,25 @@ pub struct Client {
     base_url: String,
     /// User-Agent header used by the client
     user_agent: String,
-    /// Time of the last request performed by the client
-    last_request: Mutex<Instant>,
+    /// Additional headers sent with every request
+    headers: Vec<(String, String)>,
+    /// Api token sent with every request
+    token: Option<Token>,
+    /// Rate limiter used for spacing out requests
+    rate_limiter: Arc<RateLimiter>,
+    /// Minimum interval between requests enforced by the target registry's
+    /// policy
+    rate_floor: Option<Duration>,
+    /// Policy for retrying failed requests
+    retry_policy: RetryPolicy,
+    /// Timeouts applied to http requests
+    timeouts: Timeouts,
+    /// Maximum time spent waiting for the rate limiter
+    acquire_timeout: Option<Duration>,
+    /// Cache of recent responses
+    cache: Option<Cache>,
+    /// Transport used for performing http requests
+    transport: Box<dyn Transport>,
 }
 
 impl Client {
@@ -95,95 +140,361 @@ impl Client {
     /// my_crawler (help@my_crawler.com)
     /// my_crawler (github.com/me/my_crawler)
     /// ```
+    ///
+    /// The user agent isn't validated here, use `Client::builder` to have
+    /// the configuration checked.
     pub fn new(user_agent: &str) -> Self {
         Self::new_with_base_url(BASE_URL, user_agent)
     }
 
     /// Creates a new client with the given base url and user agent string.
+    ///
+    /// Neither the user agent nor the base url are validated here, use
+    /// `Client::builder` to have the configuration checked.
     pub fn new_with_base_url(base_url: &str, user_agent: &str) -> Self {
-        Self {
-            base_url: base_url.to_string(),
-            user_agent: user_agent.to_string(),
-            last_request: Mutex::new(Instant::now() - RATE_LIMIT),
-        }
+        ClientBuilder::new(user_agent)
+            .base_url(base_url)
+            .into_client()
+    }
+
+    /// Creates a new builder for configuring a client with the given user
+    /// agent string.
+    ///
+    /// ```rust,no_run
+    /// # use std::time::Duration;
+    /// # use consecrates::{Client, HttpReqTransport};
+    /// let client = Client::builder("my_app (github.com/me/me_app)")
+    ///     .header("Accept-Language", "en")
+    ///     .transport(HttpReqTransport)
+    ///     .build()
+    ///     .expect("invalid client config");
+    /// ```
+    pub fn builder(user_agent: &str) -> ClientBuilder {
+        ClientBuilder::new(user_agent)
+    }
+
+    /// Starts building a new url on top of the base url.
+    fn url(&self) -> UrlBuilder {
+        UrlBuilder::new(&self.base_url)
     }
 
     fn url_crates(&self, query: Query) -> Result<String> {
-        // construct the target url
-        let mut url = self.base_url.clone();
-        url.push_str("crates?");
-        if let Some(page) = query.page {
-            url.push_str(&format!("page={}", page));
-        }
-        if let Some(per_page) = query.per_page {
-            url.push_str(&format!("&per_page={}", per_page));
+        if let Some(cursor) = query.cursor {
+            return Ok(self.url().path("crates").raw_query(&cursor).build());
         }
-        if let Some(sort) = query.sort {
-            url.push_str(&format!("&sort={}", sort.to_str()));
-        }
-        if let Some(query_string) = query.string {
-            url.push_str(&format!("&q={}", query_string));
-        }
-        if let Some(cat) = query.category {
-            url.push_str(&format!("&category={}", cat.to_str()));
-        }
-        if let Some(keyword) = query.keyword {
-            url.push_str(&format!("&keyword={}", keyword))
+        if let Some(letter) = query.letter {
+            if !letter.is_ascii_alphabetic() {
+                return Err(Error::InvalidQuery(format!(
+                    "letter filter must be an ascii letter, got `{}`",
+                    letter
+                )));
+            }
         }
-
+        // the api ands together keywords passed with `all_keywords`, a lone
+        // keyword is passed with the simpler `keyword` param
+        let mut keywords = query.keywords;
+        let (keyword, all_keywords) = if keywords.len() > 1 {
+            (None, Some(keywords.join(" ")))
+        } else {
+            (keywords.pop(), None)
+        };
+        let url = self
+            .url()
+            .path("crates")
+            .opt_param("page", query.page)
+            .opt_param("per_page", query.per_page)
+            .opt_param("sort", query.sort.as_ref().map(|s| s.to_str()))
+            .opt_param("q", query.string)
+            .opt_param("category", query.categories.first().map(|c| c.to_str()))
+            .opt_param("keyword", keyword)
+            .opt_param("user_id", query.user_id)
+            .opt_param("team_id", query.team_id)
+            .opt_param("letter", query.letter)
+            .opt_param("all_keywords", all_keywords)
+            .opt_param(
+                "include_yanked",
+                query.include_yanked.map(|y| if y { "yes" } else { "no" }),
+            )
+            .opt_param("following", if query.following { Some(1) } else { None })
+            .build();
         Ok(url)
     }
 
     /// Gets a page of crates, using a set of query options.
+    ///
+    /// # Query details
+    ///
+    /// The api can only filter by a single category. When the query holds
+    /// more than one, the page is filtered by the client, performing one
+    /// additional request per extra category. Filtered pages can be shorter
+    /// than `per_page` or even empty, while `meta` still describes the
+    /// listing of the first category.
     pub fn get_crates(&self, query: Query) -> Result<Crates> {
-        let crates = self.get(&self.url_crates(query)?)?;
+        let (crates, _) = self.get_crates_filtered(query)?;
         Ok(crates)
     }
 
+    /// Gets a page of crates like `get_crates`, along with the number of
+    /// crates on the page before filtering by extra categories.
+    fn get_crates_filtered(&self, query: Query) -> Result<(Crates, usize)> {
+        let extra_categories = query.categories.iter().skip(1).copied().collect::<Vec<_>>();
+        let mut crates: Crates = self.get(&self.url_crates(query)?)?;
+        let unfiltered = crates.crates.len();
+        self.retain_in_categories(&mut crates, &extra_categories)?;
+        Ok((crates, unfiltered))
+    }
+
     /// Tries to get a page of crates, using a set of query options.
+    ///
+    /// # Query details
+    ///
+    /// Filtering by multiple categories requires more than one request,
+    /// which is only supported by `get_crates`. Queries with more than one
+    /// category are rejected with `Error::InvalidQuery`.
     pub fn try_get_crates(&self, query: Query) -> Result<Crates> {
+        if query.categories.len() > 1 {
+            return Err(Error::InvalidQuery(
+                "filtering by multiple categories is only supported by get_crates".to_string(),
+            ));
+        }
         let crates = self.try_get(&self.url_crates(query)?)?;
         Ok(crates)
     }
 
+    /// Drops crates not belonging to all of the given categories, asking the
+    /// server which of the listed crates belong to each category.
+    fn retain_in_categories(&self, crates: &mut Crates, categories: &[Category]) -> Result<()> {
+        for category in categories {
+            let mut members = HashSet::new();
+            let names = crates
+                .crates
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<&str>>();
+            for chunk in names.chunks(MAX_PER_PAGE) {
+                let page: Crates = self.get(&self.url_crates_by_ids(chunk, Some(*category))?)?;
+                members.extend(page.crates.into_iter().map(|c| c.name));
+            }
+            crates.crates.retain(|c| members.contains(&c.name));
+        }
+        Ok(())
+    }
+
+    fn url_crates_by_ids(&self, crate_ids: &[&str], category: Option<Category>) -> Result<String> {
+        let url = crate_ids
+            .iter()
+            .fold(self.url().path("crates"), |url, id| url.param("ids[]", id))
+            .opt_param("category", category.as_ref().map(|c| c.to_str()))
+            .param("per_page", &crate_ids.len().to_string())
+            .build();
+        Ok(url)
+    }
+
+    /// Gets multiple crates by their names, using as few requests as
+    /// possible.
+    ///
+    /// Names are looked up in chunks of up to 100 per request. Returned map
+    /// is keyed by the names as provided, names that couldn't be found are
+    /// reported separately.
+    ///
+    /// ```rust,no_run
+    /// # use consecrates::Client;
+    /// let client = Client::new("my_app (github.com/me/me_app)");
+    /// let crates = client
+    ///     .get_crates_by_ids(&["serde", "anyhow", "no-such-crate"])
+    ///     .expect("failed getting crates");
+    /// assert_eq!(crates.missing, vec!["no-such-crate"]);
+    /// ```
+    pub fn get_crates_by_ids(&self, crate_ids: &[&str]) -> Result<CratesByIds> {
+        let mut result = CratesByIds::default();
+        for chunk in crate_ids.chunks(MAX_PER_PAGE) {
+            let crates: Crates = self.get(&self.url_crates_by_ids(chunk, None)?)?;
+            for id in chunk {
+                let normalized = normalize_crate_name(id);
+                match crates
+                    .crates
+                    .iter()
+                    .find(|c| normalize_crate_name(&c.name) == normalized)
+                {
+                    Some(crate_) => {
+                        result.found.insert(id.to_string(), crate_.clone());
+                    }
+                    None => result.missing.push(id.to_string()),
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Iterates over all crates matching the query, transparently fetching
+    /// consecutive pages.
+    ///
+    /// Iteration starts at `query.page`, or the first page if not provided.
+    /// Consecutive pages are requested using the `next_page` cursors provided
+    /// by the server, which allows walking listings deeper than the server's
+    /// page number limit.
+    ///
+    /// ```rust,no_run
+    /// # use consecrates::{Client, Query};
+    /// let client = Client::new("my_app (github.com/me/me_app)");
+    /// for crate_ in client.crates_iter(Query::from_str("net cat=gamedev")) {
+    ///     println!("{}", crate_.expect("failed getting crates").name);
+    /// }
+    /// ```
+    pub fn crates_iter(&self, query: Query) -> CratesIter<'_> {
+        Paginator::filtered(self, query, |client, query| {
+            client
+                .get_crates_filtered(query)
+                .map(|(crates, unfiltered)| (crates, Some(unfiltered)))
+        })
+    }
+
+    /// Creates a resumable crawler going through the whole crates listing,
+    /// persisting progress to the given checkpoint file.
+    pub fn crawler(&self, checkpoint_path: impl AsRef<Path>) -> Crawler<'_> {
+        Crawler::new(self, checkpoint_path.as_ref())
+    }
+
     fn url_crate(&self, crate_id: &str) -> Result<String> {
-        Ok(format!("{}crates/{}", self.base_url, crate_id))
+        Ok(self.url().path("crates").path(crate_id).build())
     }
 
     /// Gets information about a particular crate.
-    pub fn get_crate(&self, crate_id: &str) -> Result<Crate> {
+    ///
+    /// Along with the crate itself the response includes all of its versions
+    /// as well as full keyword and category objects.
+    ///
+    /// Returns `Error::NotFound` if the crate doesn't exist.
+    ///
+    /// ```rust,no_run
+    /// # use consecrates::{Client, Error};
+    /// let client = Client::new("my_app (github.com/me/me_app)");
+    /// match client.get_crate("no-such-crate") {
+    ///     Err(Error::NotFound { detail, .. }) => println!("missing: {:?}", detail),
+    ///     Ok(response) => println!("{:?}", response.crate_data),
+    ///     Err(e) => println!("{}", e),
+    /// }
+    /// ```
+    pub fn get_crate(&self, crate_id: &str) -> Result<CrateResponse> {
         let crate_ = self.get(&self.url_crate(crate_id)?)?;
         Ok(crate_)
     }
 
     /// Tries to get information about a particular crate.
-    pub fn try_get_crate(&self, crate_id: &str) -> Result<Crate> {
+    pub fn try_get_crate(&self, crate_id: &str) -> Result<CrateResponse> {
         let crate_ = self.try_get(&self.url_crate(crate_id)?)?;
         Ok(crate_)
     }
 
     fn url_crate_version(&self, crate_id: &str, crate_version: &str) -> Result<String> {
-        Ok(format!(
-            "{}crates/{}/{}",
-            self.base_url, crate_id, crate_version
-        ))
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path(crate_version)
+            .build())
     }
 
     /// Gets crate information for a particular version of the given crate.
     pub fn get_crate_version(&self, crate_id: &str, crate_version: &str) -> Result<Version> {
-        let version = self.get(&self.url_crate_version(crate_id, crate_version)?)?;
-        Ok(version)
+        let response: VersionResponse =
+            self.get(&self.url_crate_version(crate_id, crate_version)?)?;
+        Ok(response.version)
     }
 
     /// Tries to get crate information for a particular version of the given
     /// crate.
     pub fn try_get_crate_version(&self, crate_id: &str, crate_version: &str) -> Result<Version> {
-        let version = self.try_get(&self.url_crate_version(crate_id, crate_version)?)?;
-        Ok(version)
+        let response: VersionResponse =
+            self.try_get(&self.url_crate_version(crate_id, crate_version)?)?;
+        Ok(response.version)
+    }
+
+    fn url_crate_versions(
+        &self,
+        crate_id: &str,
+        sort: Option<VersionSorting>,
+        query: Query,
+    ) -> Result<String> {
+        let url = self.url().path("crates").path(crate_id).path("versions");
+        if let Some(cursor) = query.cursor {
+            return Ok(url.raw_query(&cursor).build());
+        }
+        let url = url
+            .opt_param("page", query.page)
+            .opt_param("per_page", query.per_page)
+            .opt_param("sort", sort.as_ref().map(|s| s.to_str()))
+            .build();
+        Ok(url)
+    }
+
+    /// Gets a page of versions of the given crate.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`,
+    /// `per_page` and `cursor` fields.
+    pub fn get_crate_versions(
+        &self,
+        crate_id: &str,
+        sort: Option<VersionSorting>,
+        query: Query,
+    ) -> Result<Versions> {
+        let versions = self.get(&self.url_crate_versions(crate_id, sort, query)?)?;
+        Ok(versions)
+    }
+
+    /// Tries to get a page of versions of the given crate.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`,
+    /// `per_page` and `cursor` fields.
+    pub fn try_get_crate_versions(
+        &self,
+        crate_id: &str,
+        sort: Option<VersionSorting>,
+        query: Query,
+    ) -> Result<Versions> {
+        let versions = self.try_get(&self.url_crate_versions(crate_id, sort, query)?)?;
+        Ok(versions)
+    }
+
+    /// Iterates over all versions of the given crate, transparently fetching
+    /// consecutive pages.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`,
+    /// `per_page` and `cursor` fields.
+    ///
+    /// ```rust,no_run
+    /// # use consecrates::{Client, Query, VersionSorting};
+    /// let client = Client::new("my_app (github.com/me/me_app)");
+    /// let versions = client.crate_versions_iter("serde", Some(VersionSorting::Date), Query::default());
+    /// for version in versions {
+    ///     let version = version.expect("failed getting versions");
+    ///     println!("{} released {}", version.num, version.created_at);
+    /// }
+    /// ```
+    pub fn crate_versions_iter<'a>(
+        &'a self,
+        crate_id: &'a str,
+        sort: Option<VersionSorting>,
+        query: Query,
+    ) -> VersionsIter<'a> {
+        Paginator::new(self, query, move |client, query| {
+            client.get_crate_versions(crate_id, sort, query)
+        })
     }
 
     fn url_crate_downloads(&self, crate_id: &str) -> Result<String> {
-        Ok(format!("{}crates/{}/downloads", self.base_url, crate_id))
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path("downloads")
+            .build())
     }
 
     /// Gets information about the download stats for the given crate.
@@ -198,11 +509,48 @@ impl Client {
         Ok(downloads)
     }
 
+    fn url_version_downloads(&self, crate_id: &str, crate_version: &str) -> Result<String> {
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path(crate_version)
+            .path("downloads")
+            .build())
+    }
+
+    /// Gets the daily download stats for a particular version of the given
+    /// crate.
+    pub fn get_version_downloads(
+        &self,
+        crate_id: &str,
+        crate_version: &str,
+    ) -> Result<Vec<VersionDownloads>> {
+        let response: VersionDownloadsResponse =
+            self.get(&self.url_version_downloads(crate_id, crate_version)?)?;
+        Ok(response.version_downloads)
+    }
+
+    /// Tries to get the daily download stats for a particular version of the
+    /// given crate.
+    pub fn try_get_version_downloads(
+        &self,
+        crate_id: &str,
+        crate_version: &str,
+    ) -> Result<Vec<VersionDownloads>> {
+        let response: VersionDownloadsResponse =
+            self.try_get(&self.url_version_downloads(crate_id, crate_version)?)?;
+        Ok(response.version_downloads)
+    }
+
     fn url_crate_dependencies(&self, crate_id: &str, crate_version: &str) -> Result<String> {
-        Ok(format!(
-            "{}crates/{}/{}/dependencies",
-            self.base_url, crate_id, crate_version
-        ))
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path(crate_version)
+            .path("dependencies")
+            .build())
     }
 
     /// Gets a list of dependencies for a particular version of the given crate.
@@ -222,12 +570,96 @@ impl Client {
         crate_id: &str,
         crate_version: &str,
     ) -> Result<Dependencies> {
-        let dependencies = self.get(&self.url_crate_dependencies(crate_id, crate_version)?)?;
+        let dependencies = self.try_get(&self.url_crate_dependencies(crate_id, crate_version)?)?;
         Ok(dependencies)
     }
 
+    fn url_crate_reverse_dependencies(&self, crate_id: &str, query: Query) -> Result<String> {
+        let url = self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path("reverse_dependencies")
+            .opt_param("page", query.page)
+            .opt_param("per_page", query.per_page)
+            .build();
+        Ok(url)
+    }
+
+    /// Gets a page of dependency records pointing at the given crate, along
+    /// with the dependent crate versions they belong to.
+    ///
+    /// Use `ReverseDependencies::into_pairs` to match records with versions.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`
+    /// and `per_page` fields.
+    pub fn get_crate_reverse_dependencies(
+        &self,
+        crate_id: &str,
+        query: Query,
+    ) -> Result<ReverseDependencies> {
+        let reverse_dependencies =
+            self.get(&self.url_crate_reverse_dependencies(crate_id, query)?)?;
+        Ok(reverse_dependencies)
+    }
+
+    /// Tries to get a page of dependency records pointing at the given crate,
+    /// along with the dependent crate versions they belong to.
+    ///
+    /// Use `ReverseDependencies::into_pairs` to match records with versions.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`
+    /// and `per_page` fields.
+    pub fn try_get_crate_reverse_dependencies(
+        &self,
+        crate_id: &str,
+        query: Query,
+    ) -> Result<ReverseDependencies> {
+        let reverse_dependencies =
+            self.try_get(&self.url_crate_reverse_dependencies(crate_id, query)?)?;
+        Ok(reverse_dependencies)
+    }
+
+    /// Iterates over all dependents of the given crate, transparently
+    /// fetching consecutive pages.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`
+    /// and `per_page` fields.
+    ///
+    /// ```rust,no_run
+    /// # use consecrates::{Client, Query};
+    /// let client = Client::new("my_app (github.com/me/me_app)");
+    /// for dependent in client.reverse_dependencies_iter("serde", Query::default()) {
+    ///     let dependent = dependent.expect("failed getting dependents");
+    ///     if let Some(version) = dependent.version {
+    ///         let req = dependent.dependency.req;
+    ///         println!("{} {} requires {}", version.crate_name, version.num, req);
+    ///     }
+    /// }
+    /// ```
+    pub fn reverse_dependencies_iter<'a>(
+        &'a self,
+        crate_id: &'a str,
+        query: Query,
+    ) -> ReverseDependenciesIter<'a> {
+        Paginator::new(self, query, move |client, query| {
+            client.get_crate_reverse_dependencies(crate_id, query)
+        })
+    }
+
     fn url_crate_owners(&self, crate_id: &str) -> Result<String> {
-        Ok(format!("{}crates/{}/owners", self.base_url, crate_id))
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path("owners")
+            .build())
     }
 
     /// Gets information about the owners of the given crate.
@@ -242,48 +674,250 @@ impl Client {
         Ok(owners)
     }
 
+    fn url_crate_owner_users(&self, crate_id: &str) -> Result<String> {
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path("owner_user")
+            .build())
+    }
+
+    /// Gets the individual users owning the given crate.
+    pub fn get_crate_owner_users(&self, crate_id: &str) -> Result<Owners> {
+        let owners = self.get(&self.url_crate_owner_users(crate_id)?)?;
+        Ok(owners)
+    }
+
+    /// Tries to get the individual users owning the given crate.
+    pub fn try_get_crate_owner_users(&self, crate_id: &str) -> Result<Owners> {
+        let owners = self.try_get(&self.url_crate_owner_users(crate_id)?)?;
+        Ok(owners)
+    }
+
+    fn url_crate_owner_teams(&self, crate_id: &str) -> Result<String> {
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path("owner_team")
+            .build())
+    }
+
+    /// Gets the teams owning the given crate.
+    pub fn get_crate_owner_teams(&self, crate_id: &str) -> Result<TeamOwners> {
+        let owners = self.get(&self.url_crate_owner_teams(crate_id)?)?;
+        Ok(owners)
+    }
+
+    /// Tries to get the teams owning the given crate.
+    pub fn try_get_crate_owner_teams(&self, crate_id: &str) -> Result<TeamOwners> {
+        let owners = self.try_get(&self.url_crate_owner_teams(crate_id)?)?;
+        Ok(owners)
+    }
+
     fn url_crate_authors(&self, crate_id: &str, crate_version: &str) -> Result<String> {
-        Ok(format!(
-            "{}crates/{}/{}/authors",
-            self.base_url, crate_id, crate_version
-        ))
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path(crate_version)
+            .path("authors")
+            .build())
     }
 
     /// Gets information about the authors for a particular version of the
     /// given crate.
     pub fn get_crate_authors(&self, crate_id: &str, crate_version: &str) -> Result<Authors> {
-        let authors = self.get(&self.url_crate_authors(crate_id, crate_version)?)?;
-        Ok(authors)
+        let response: AuthorsResponse =
+            self.get(&self.url_crate_authors(crate_id, crate_version)?)?;
+        Ok(response.into())
     }
 
     /// Tries to get information about the authors for a particular version of
     /// the given crate.
     pub fn try_get_crate_authors(&self, crate_id: &str, crate_version: &str) -> Result<Authors> {
-        let authors = self.try_get(&self.url_crate_authors(crate_id, crate_version)?)?;
-        Ok(authors)
+        let response: AuthorsResponse =
+            self.try_get(&self.url_crate_authors(crate_id, crate_version)?)?;
+        Ok(response.into())
     }
 
     fn url_crate_readme(&self, crate_id: &str, crate_version: &str) -> Result<String> {
-        Ok(format!(
-            "{}crates/{}/{}/readme",
-            self.base_url, crate_id, crate_version
-        ))
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path(crate_version)
+            .path("readme")
+            .build())
     }
 
     /// Gets the readme for a particular version of the given crate.
+    ///
+    /// Readme is returned as rendered html.
     pub fn get_crate_readme(&self, crate_id: &str, crate_version: &str) -> Result<String> {
-        let readme = self.get(&self.url_crate_readme(crate_id, crate_version)?)?;
+        let readme = self.get_text(&self.url_crate_readme(crate_id, crate_version)?)?;
         Ok(readme)
     }
 
     /// Tries to get the readme for a particular version of the given crate.
+    ///
+    /// Readme is returned as rendered html.
     pub fn try_get_crate_readme(&self, crate_id: &str, crate_version: &str) -> Result<String> {
-        let readme = self.try_get(&self.url_crate_readme(crate_id, crate_version)?)?;
+        let readme = self.try_get_text(&self.url_crate_readme(crate_id, crate_version)?)?;
         Ok(readme)
     }
 
+    fn url_user(&self, login: &str) -> Result<String> {
+        Ok(self.url().path("users").path(login).build())
+    }
+
+    /// Gets information about the user with the given login.
+    ///
+    /// To list crates owned by the user pass its `id` as `Query::user_id`
+    /// to `get_crates`.
+    pub fn get_user(&self, login: &str) -> Result<User> {
+        let response: UserResponse = self.get(&self.url_user(login)?)?;
+        Ok(response.user)
+    }
+
+    /// Tries to get information about the user with the given login.
+    pub fn try_get_user(&self, login: &str) -> Result<User> {
+        let response: UserResponse = self.try_get(&self.url_user(login)?)?;
+        Ok(response.user)
+    }
+
+    fn url_me(&self) -> Result<String> {
+        Ok(self.url().path("me").build())
+    }
+
+    /// Gets the account the client's token belongs to, along with the
+    /// crates it owns.
+    ///
+    /// Requires a client with a token, see `ClientBuilder::token`.
+    pub fn get_me(&self) -> Result<Me> {
+        let me = self.get(&self.url_me()?)?;
+        Ok(me)
+    }
+
+    /// Tries to get the account the client's token belongs to, along with
+    /// the crates it owns.
+    ///
+    /// Requires a client with a token, see `ClientBuilder::token`.
+    pub fn try_get_me(&self) -> Result<Me> {
+        let me = self.try_get(&self.url_me()?)?;
+        Ok(me)
+    }
+
+    /// Gets a page of crates owned by the account the client's token
+    /// belongs to.
+    ///
+    /// Looks up the account with `get_me` first, then lists its crates
+    /// with `Query::user_id` set.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object and can use all of its fields
+    /// except for `user_id` and `team_id`.
+    pub fn get_my_crates(&self, query: Query) -> Result<Crates> {
+        let me = self.get_me()?;
+        self.get_crates(Query {
+            user_id: Some(me.user.id),
+            team_id: None,
+            ..query
+        })
+    }
+
+    fn url_my_updates(&self, query: Query) -> Result<String> {
+        let url = self
+            .url()
+            .path("me")
+            .path("updates")
+            .opt_param("page", query.page)
+            .opt_param("per_page", query.per_page)
+            .build();
+        Ok(url)
+    }
+
+    /// Gets the most recently published versions of crates followed by the
+    /// account the client's token belongs to.
+    ///
+    /// Requires a client with a token, see `ClientBuilder::token`.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`
+    /// and `per_page` fields.
+    pub fn get_my_updates(&self, query: Query) -> Result<Updates> {
+        let updates = self.get(&self.url_my_updates(query)?)?;
+        Ok(updates)
+    }
+
+    /// Tries to get the most recently published versions of crates followed
+    /// by the account the client's token belongs to.
+    ///
+    /// Requires a client with a token, see `ClientBuilder::token`.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`
+    /// and `per_page` fields.
+    pub fn try_get_my_updates(&self, query: Query) -> Result<Updates> {
+        let updates = self.try_get(&self.url_my_updates(query)?)?;
+        Ok(updates)
+    }
+
+    fn url_crate_following(&self, crate_id: &str) -> Result<String> {
+        Ok(self
+            .url()
+            .path("crates")
+            .path(crate_id)
+            .path("following")
+            .build())
+    }
+
+    /// Checks whether the account the client's token belongs to follows
+    /// the given crate.
+    ///
+    /// Requires a client with a token, see `ClientBuilder::token`. To list
+    /// all followed crates set `Query::following` with `get_crates`.
+    pub fn get_crate_following(&self, crate_id: &str) -> Result<bool> {
+        let response: FollowingResponse = self.get(&self.url_crate_following(crate_id)?)?;
+        Ok(response.following)
+    }
+
+    /// Tries to check whether the account the client's token belongs to
+    /// follows the given crate.
+    ///
+    /// Requires a client with a token, see `ClientBuilder::token`.
+    pub fn try_get_crate_following(&self, crate_id: &str) -> Result<bool> {
+        let response: FollowingResponse = self.try_get(&self.url_crate_following(crate_id)?)?;
+        Ok(response.following)
+    }
+
+    fn url_team(&self, name: &str) -> Result<String> {
+        Ok(self.url().path("teams").path(name).build())
+    }
+
+    /// Gets information about the team with the given name, e.g.
+    /// `github:rust-lang:core`.
+    ///
+    /// To list crates owned by the team pass its `id` as `Query::team_id`
+    /// to `get_crates`.
+    pub fn get_team(&self, name: &str) -> Result<Team> {
+        let response: TeamResponse = self.get(&self.url_team(name)?)?;
+        Ok(response.team)
+    }
+
+    /// Tries to get information about the team with the given name, e.g.
+    /// `github:rust-lang:core`.
+    pub fn try_get_team(&self, name: &str) -> Result<Team> {
+        let response: TeamResponse = self.try_get(&self.url_team(name)?)?;
+        Ok(response.team)
+    }
+
     fn url_registry_summary(&self) -> Result<String> {
-        Ok(format!("{}summary", self.base_url))
+        Ok(self.url().path("summary").build())
     }
 
     /// Gets registry-wide summary.
@@ -302,16 +936,15 @@ impl Client {
         let mut cat_string = None;
         if let Some(s) = query.string {
             cat_string = Some(s);
-        } else if let Some(cat) = query.category {
+        } else if let Some(cat) = query.categories.first() {
             cat_string = Some(cat.to_str().to_string());
         }
 
         if let Some(cats) = cat_string {
-            let url = format!("{}categories/{}", self.base_url, &cats);
-            Ok(url)
+            Ok(self.url().path("categories").path(&cats).build())
         } else {
-            Err(Error::msg(
-                "didn't provide either a string or category argument with query",
+            Err(Error::InvalidQuery(
+                "didn't provide either a string or category argument with query".to_string(),
             ))
         }
     }
@@ -321,7 +954,7 @@ impl Client {
     /// # Query details
     ///
     /// This function accepts a `Query` object but can only use it's `string`
-    /// or `category` fields.
+    /// or `categories` fields, using only the first category.
     pub fn get_category(&self, query: Query) -> Result<api::Category> {
         let category = self.get(&self.url_category(query)?)?;
         Ok(category)
@@ -332,21 +965,19 @@ impl Client {
     /// # Query details
     ///
     /// This function accepts a `Query` object but can only use it's `string`
-    /// or `category` fields.
+    /// or `categories` fields, using only the first category.
     pub fn try_get_category(&self, query: Query) -> Result<api::Category> {
         let category = self.try_get(&self.url_category(query)?)?;
         Ok(category)
     }
 
     fn url_categories(&self, query: Query) -> Result<String> {
-        let mut url = self.base_url.clone();
-        url.push_str("categories?");
-        if let Some(page) = query.page {
-            url.push_str(&format!("page={}", page));
-        }
-        if let Some(per_page) = query.per_page {
-            url.push_str(&format!("&per_page={}", per_page));
-        }
+        let url = self
+            .url()
+            .path("categories")
+            .opt_param("page", query.page)
+            .opt_param("per_page", query.per_page)
+            .build();
         Ok(url)
     }
 
@@ -372,21 +1003,30 @@ impl Client {
         Ok(categories)
     }
 
+    /// Iterates over all categories available with the registry,
+    /// transparently fetching consecutive pages.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`
+    /// and `per_page` fields.
+    pub fn categories_iter(&self, query: Query) -> CategoriesIter<'_> {
+        Paginator::new(self, query, |client, query| client.get_categories(query))
+    }
+
     fn url_keyword(&self, query: Query) -> Result<String> {
         let mut key_string = None;
         if let Some(s) = query.string {
             key_string = Some(s);
-        } else if let Some(key) = query.keyword {
+        } else if let Some(key) = query.keywords.into_iter().next() {
             key_string = Some(key);
         }
 
         if let Some(keys) = key_string {
-            let mut url = self.base_url.clone();
-            url.push_str(&format!("keywords/{}", &keys));
-            Ok(url)
+            Ok(self.url().path("keywords").path(&keys).build())
         } else {
-            Err(Error::msg(
-                "didn't provide either a string or keyword argument with query",
+            Err(Error::InvalidQuery(
+                "didn't provide either a string or keyword argument with query".to_string(),
             ))
         }
     }
@@ -396,7 +1036,7 @@ impl Client {
     /// # Query details
     ///
     /// This function accepts a `Query` object but can only use it's `string`
-    /// or `keyword` fields.
+    /// or `keywords` fields, using only the first keyword.
     pub fn get_keyword(&self, query: Query) -> Result<api::Keyword> {
         let keyword = self.get(&self.url_keyword(query)?)?;
         Ok(keyword)
@@ -407,21 +1047,19 @@ impl Client {
     /// # Query details
     ///
     /// This function accepts a `Query` object but can only use it's `string`
-    /// or `keyword` fields.
+    /// or `keywords` fields, using only the first keyword.
     pub fn try_get_keyword(&self, query: Query) -> Result<api::Keyword> {
         let keyword = self.try_get(&self.url_keyword(query)?)?;
         Ok(keyword)
     }
 
     fn url_keywords(&self, query: Query) -> Result<String> {
-        let mut url = self.base_url.clone();
-        url.push_str("keywords?");
-        if let Some(page) = query.page {
-            url.push_str(&format!("page={}", page));
-        }
-        if let Some(per_page) = query.per_page {
-            url.push_str(&format!("&per_page={}", per_page));
-        }
+        let url = self
+            .url()
+            .path("keywords")
+            .opt_param("page", query.page)
+            .opt_param("per_page", query.per_page)
+            .build();
         Ok(url)
     }
 
@@ -448,50 +1086,154 @@ impl Client {
         Ok(keywords)
     }
 
+    /// Iterates over all keywords used by crates within the registry,
+    /// transparently fetching consecutive pages.
+    ///
+    /// # Query details
+    ///
+    /// This function accepts a `Query` object but can only use it's `page`
+    /// and `per_page` fields.
+    ///
+    /// ```rust,no_run
+    /// # use consecrates::{Client, Query};
+    /// let client = Client::new("my_app (github.com/me/me_app)");
+    /// let keywords = client
+    ///     .keywords_iter(Query::default())
+    ///     .max_items(500)
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .expect("failed getting keywords");
+    /// ```
+    pub fn keywords_iter(&self, query: Query) -> KeywordsIter<'_> {
+        Paginator::new(self, query, |client, query| client.get_keywords(query))
+    }
+
     fn get<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
-        // block until it's been long enough since the last request
+        self.block(|| self.try_get(url))
+    }
+
+    fn get_text(&self, url: &str) -> Result<String> {
+        self.block(|| self.try_get_text(url))
+    }
+
+    /// Repeats the given non-blocking operation until the rate limiter
+    /// allows it through, retrying transient failures according to the
+    /// retry policy.
+    fn block<T>(&self, try_op: impl Fn() -> Result<T>) -> Result<T> {
+        let mut attempt = 0;
+        let mut waiting_since = Instant::now();
         loop {
-            match self.try_get(url) {
+            match try_op() {
+                // block until it's been long enough since the last request
+                Err(Error::RateLimited) => {
+                    if let Some(timeout) = self.acquire_timeout {
+                        if waiting_since.elapsed() >= timeout {
+                            return Err(Error::Timeout);
+                        }
+                    }
+                    std::thread::sleep(Duration::from_millis(60));
+                    continue;
+                }
                 Err(error) => {
-                    if std::io::ErrorKind::WouldBlock == error.kind() {
-                        std::thread::sleep(Duration::from_millis(60));
-                        continue;
-                    } else {
-                        return Err(Error::from(error));
+                    attempt += 1;
+                    waiting_since = Instant::now();
+                    match self.retry_policy.next_delay(attempt, &error) {
+                        Some(delay) => std::thread::sleep(delay),
+                        None => return Err(error),
                     }
                 }
-                Ok(response) => return Ok(response),
+                result => return result,
             }
         }
     }
 
-    /// Tries to get data from the provided url.
-    ///
-    /// Only returns response body.
+    /// Tries to get data from the provided url, deserializing the json
+    /// response body.
+    fn try_get<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self.try_send(url)?;
+        serde_json::from_slice(&response.body).map_err(|e| {
+            // some endpoints report errors with a success status code
+            match serde_json::from_slice::<ApiErrors>(&response.body) {
+                Ok(payload) => Error::Api {
+                    code: response.status,
+                    errors: payload.errors,
+                    retry_after: None,
+                },
+                Err(_) => Error::json(e, &response.body),
+            }
+        })
+    }
+
+    /// Tries to get data from the provided url, returning the response body
+    /// as text.
+    fn try_get_text(&self, url: &str) -> Result<String> {
+        let response = self.try_send(url)?;
+        Ok(String::from_utf8_lossy(&response.body).into_owned())
+    }
+
+    /// Tries to perform a request to the provided url.
     ///
     /// # Semi-non-blocking
     ///
-    /// Returns an error if client is waiting for rate limiter to allow
-    /// processing next request. Processing http request itself will block.
-    fn try_get<T: DeserializeOwned>(&self, url: &str) -> std::io::Result<T> {
-        let mut lr = self.last_request.lock().unwrap();
-        if lr.elapsed() >= RATE_LIMIT {
-            *lr = Instant::now();
-        } else {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::WouldBlock,
-                Error::msg("Would block"),
-            ));
+    /// Returns `Error::RateLimited` if client is waiting for rate limiter to
+    /// allow processing next request. Processing http request itself will
+    /// block.
+    fn try_send(&self, url: &str) -> Result<transport::Response> {
+        if let Some(response) = self.cache.as_ref().and_then(|c| c.get(url)) {
+            return Ok(response);
+        }
+
+        self.rate_limiter.try_acquire(self.rate_floor)?;
+        let mut headers = vec![("User-Agent".to_string(), self.user_agent.clone())];
+        headers.extend(self.headers.iter().cloned());
+        if let Some(token) = &self.token {
+            headers.push(("Authorization".to_string(), token.as_str().to_string()));
         }
-        let mut buffer = Vec::new();
-        let uri =
-            Uri::try_from(url).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        let _ = Request::new(&uri)
-            .header("User-Agent", &self.user_agent)
-            .send(&mut buffer)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
-        let deser: T = serde_json::from_slice(&buffer)?;
-        Ok(deser)
+        let request = transport::Request {
+            url: url.to_string(),
+            headers,
+            timeouts: self.timeouts,
+        };
+        let response = self.transport.send(&request)?;
+
+        if !(200..300).contains(&response.status) {
+            return Err(status_error(url, &response));
+        }
+        if let Some(cache) = &self.cache {
+            cache.insert(url, &response);
+        }
+        Ok(response)
+    }
+}
+
+/// Normalizes a crate name for comparison, as `crates.io` treats names
+/// differing only in case or `-` and `_` as the same crate.
+fn normalize_crate_name(name: &str) -> String {
+    name.to_ascii_lowercase().replace('_', "-")
+}
+
+/// Turns a response with a non-success status code into an error, decoding
+/// the `crates.io` error payload if present.
+fn status_error(url: &str, response: &transport::Response) -> Error {
+    let payload = serde_json::from_slice::<ApiErrors>(&response.body).ok();
+    let retry_after = response
+        .header("Retry-After")
+        .and_then(retry::parse_retry_after);
+    match (response.status, payload) {
+        (404, payload) => Error::NotFound {
+            url: url.to_string(),
+            detail: payload
+                .and_then(|p| p.errors.into_iter().next())
+                .map(|e| e.detail),
+        },
+        (code, Some(payload)) => Error::Api {
+            code,
+            errors: payload.errors,
+            retry_after,
+        },
+        (code, None) => Error::Status {
+            code,
+            snippet: error::snippet(&response.body),
+            retry_after,
+        },
     }
 }
@@ -0,0 +1,224 @@
+//! Iterating over paged listings.
+
+use std::collections::VecDeque;
+
+use crate::api::{
+    Categories, Category, Crate, Crates, Keyword, Keywords, ReverseDependencies, ReverseDependency,
+    Version, Versions,
+};
+use crate::{Client, Query, Result};
+
+/// Single page of a paged listing.
+pub trait Page {
+    /// Type of the listed items
+    type Item;
+
+    /// Whether the server links to the next page with `next_page`. Linked
+    /// pages are requested by passing `next_page` as `Query::cursor`,
+    /// listings without links are paged by incrementing the page number.
+    /// Linked listings fall back to page numbers as well if the server
+    /// omits the link before the total number of items was returned.
+    const LINKS_NEXT_PAGE: bool = false;
+
+    /// Total number of items in the listing, across all pages.
+    fn total(&self) -> u64;
+
+    /// Query string pointing at the next page, if provided by the server.
+    fn next_page(&self) -> Option<&str> {
+        None
+    }
+
+    /// Consumes the page returning the items.
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+impl Page for Crates {
+    type Item = Crate;
+    const LINKS_NEXT_PAGE: bool = true;
+
+    fn total(&self) -> u64 {
+        self.meta.total
+    }
+
+    fn next_page(&self) -> Option<&str> {
+        self.meta.next_page.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Crate> {
+        self.crates
+    }
+}
+
+impl Page for Categories {
+    type Item = Category;
+
+    fn total(&self) -> u64 {
+        self.meta.total
+    }
+
+    fn into_items(self) -> Vec<Category> {
+        self.categories
+    }
+}
+
+impl Page for Keywords {
+    type Item = Keyword;
+
+    fn total(&self) -> u64 {
+        self.meta.total
+    }
+
+    fn into_items(self) -> Vec<Keyword> {
+        self.keywords
+    }
+}
+
+impl Page for Versions {
+    type Item = Version;
+    const LINKS_NEXT_PAGE: bool = true;
+
+    fn total(&self) -> u64 {
+        self.meta.total
+    }
+
+    fn next_page(&self) -> Option<&str> {
+        self.meta.next_page.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Version> {
+        self.versions
+    }
+}
+
+impl Page for ReverseDependencies {
+    type Item = ReverseDependency;
+
+    fn total(&self) -> u64 {
+        self.meta.total
+    }
+
+    fn into_items(self) -> Vec<ReverseDependency> {
+        self.into_pairs()
+    }
+}
+
+/// Gets a single page of a listing for the given query, along with the
+/// number of items the server returned if the page was filtered by the
+/// client afterwards.
+type FetchPage<'a, P> = dyn Fn(&Client, Query) -> Result<(P, Option<usize>)> + 'a;
+
+/// Iterator over all items of a paged listing, fetching consecutive pages
+/// as needed.
+///
+/// Pages are fetched with the blocking `get_*` methods, respecting the
+/// client's rate limiter. Iteration stops after the last page, once the
+/// listing's total number of items was returned, or once the optional
+/// maximum item count is reached. The first error is returned as an item,
+/// after which the iterator is done.
+pub struct Paginator<'a, P: Page> {
+    client: &'a Client,
+    query: Query,
+    fetch: Box<FetchPage<'a, P>>,
+    buffer: VecDeque<P::Item>,
+    fetched: u64,
+    returned: u64,
+    max_items: Option<u64>,
+    done: bool,
+}
+
+/// Iterator over crates, see `Client::crates_iter`.
+pub type CratesIter<'a> = Paginator<'a, Crates>;
+/// Iterator over categories, see `Client::categories_iter`.
+pub type CategoriesIter<'a> = Paginator<'a, Categories>;
+/// Iterator over keywords, see `Client::keywords_iter`.
+pub type KeywordsIter<'a> = Paginator<'a, Keywords>;
+/// Iterator over versions of a crate, see `Client::crate_versions_iter`.
+pub type VersionsIter<'a> = Paginator<'a, Versions>;
+/// Iterator over dependents of a crate, see
+/// `Client::reverse_dependencies_iter`.
+pub type ReverseDependenciesIter<'a> = Paginator<'a, ReverseDependencies>;
+
+impl<'a, P: Page> Paginator<'a, P> {
+    /// Creates a new paginator starting at `query.page`, or the first page
+    /// if not provided, with `fetch` getting a single page for the query.
+    pub(crate) fn new(
+        client: &'a Client,
+        query: Query,
+        fetch: impl Fn(&Client, Query) -> Result<P> + 'a,
+    ) -> Self {
+        Self::filtered(client, query, move |client, query| {
+            fetch(client, query).map(|page| (page, None))
+        })
+    }
+
+    /// Creates a new paginator over pages filtered by the client, with
+    /// `fetch` also returning the number of items before filtering, which
+    /// is what the listing's total and page numbers refer to.
+    pub(crate) fn filtered(
+        client: &'a Client,
+        query: Query,
+        fetch: impl Fn(&Client, Query) -> Result<(P, Option<usize>)> + 'a,
+    ) -> Self {
+        Self {
+            client,
+            query,
+            fetch: Box::new(fetch),
+            buffer: VecDeque::new(),
+            fetched: 0,
+            returned: 0,
+            max_items: None,
+            done: false,
+        }
+    }
+
+    /// Stops the iteration after returning at most `max_items` items.
+    pub fn max_items(mut self, max_items: u64) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    fn fetch_page(&mut self) -> Result<()> {
+        let (page, unfiltered) = (self.fetch)(self.client, self.query.clone())?;
+        let total = page.total();
+        let next_page = page.next_page().map(|cursor| cursor.to_string());
+        let items = page.into_items();
+        let received = unfiltered.unwrap_or(items.len());
+        self.fetched += received as u64;
+
+        let current_page = self.query.page.unwrap_or(1);
+        if self.fetched >= total {
+            self.done = true;
+        } else if let Some(cursor) = next_page.filter(|_| P::LINKS_NEXT_PAGE) {
+            self.query.cursor = Some(cursor);
+        } else if received == 0 || self.query.cursor.is_some() {
+            self.done = true;
+        } else {
+            // registries not linking the next page are paged by number
+            self.query.page = Some(current_page + 1);
+        }
+        self.buffer.extend(items);
+        Ok(())
+    }
+}
+
+impl<'a, P: Page> Iterator for Paginator<'a, P> {
+    type Item = Result<P::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(max_items) = self.max_items {
+            if self.returned >= max_items {
+                return None;
+            }
+        }
+        // filtered pages may come back empty while the listing goes on
+        while self.buffer.is_empty() && !self.done {
+            if let Err(e) = self.fetch_page() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        let item = self.buffer.pop_front()?;
+        self.returned += 1;
+        Some(Ok(item))
+    }
+}
@@ -1,6 +1,7 @@
 //! Querying-related structures.
 
 /// Describes options for a single query.
+#[derive(Debug, Clone)]
 pub struct Query {
     /// String passed to the api as `query`
     pub string: Option<String>,
@@ -8,12 +9,30 @@ pub struct Query {
     pub page: Option<usize>,
     /// Number of results per page
     pub per_page: Option<usize>,
-    /// Match crates that contain a certain string keyword
-    pub keyword: Option<String>,
-    /// Specify one of the available categories
-    pub category: Option<Category>,
+    /// Match crates that contain all of the given keywords
+    pub keywords: Vec<String>,
+    /// Match crates that belong to all of the given categories. The api
+    /// only filters by the first one, the rest are checked by the client
+    /// with additional requests
+    pub categories: Vec<Category>,
     /// Sort the results on the API query level
     pub sort: Option<Sorting>,
+    /// Match crates owned by the user with the given id
+    pub user_id: Option<u64>,
+    /// Match crates owned by the team with the given id
+    pub team_id: Option<u64>,
+    /// Match crates whose name starts with the given ascii letter
+    pub letter: Option<char>,
+    /// Whether to include crates with all of their versions yanked, the api
+    /// includes them by default
+    pub include_yanked: Option<bool>,
+    /// Match only crates followed by the authenticated user, requires a
+    /// client with a token, see `ClientBuilder::token`
+    pub following: bool,
+    /// Opaque cursor taken verbatim from `PagingMeta.next_page`, e.g.
+    /// `?seek=WzEsMl0&per_page=100`. When present it's used as the whole
+    /// query string of a crates listing and all other fields are ignored
+    pub cursor: Option<String>,
 }
 
 impl Default for Query {
@@ -22,9 +41,15 @@ impl Default for Query {
             string: None,
             page: None,
             per_page: Some(100),
-            keyword: None,
-            category: None,
+            keywords: Vec::new(),
+            categories: Vec::new(),
             sort: None,
+            user_id: None,
+            team_id: None,
+            letter: None,
+            include_yanked: None,
+            following: false,
+            cursor: None,
         }
     }
 }
@@ -47,6 +72,27 @@ impl Query {
     /// ```text
     /// net cat=gamedev sort=rdl
     /// ```
+    ///
+    /// List crates owned by the user with id `42`, most downloaded first:
+    ///
+    /// ```text
+    /// user=42 sort=dl
+    /// ```
+    ///
+    /// Browse crates starting with `s` tagged with both `async` and `http`,
+    /// leaving out the ones with all versions yanked:
+    ///
+    /// ```text
+    /// letter=s kw=async kw=http yanked=no
+    /// ```
+    ///
+    /// Keywords and categories can be repeated, matching only crates that
+    /// have all of them:
+    ///
+    /// ```text
+    /// cat=async cat=net all_keywords=tokio,http
+    /// ```
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &str) -> Self {
         let mut query = Query::default();
         // split on whitespaces
@@ -54,12 +100,38 @@ impl Query {
         for s in split {
             if s.contains("cat=") || s.contains("category=") {
                 if !s.ends_with("=") {
-                    query.category = Category::from_str(s.split("=").collect::<Vec<&str>>()[1]);
+                    if let Some(category) =
+                        Category::from_str(s.split("=").collect::<Vec<&str>>()[1])
+                    {
+                        push_unique(&mut query.categories, category);
+                    }
+                }
+            } else if s.contains("all_keywords=") || s.contains("all_kw=") {
+                for keyword in s.split("=").collect::<Vec<&str>>()[1].split(",") {
+                    if !keyword.is_empty() {
+                        push_unique(&mut query.keywords, keyword.to_string());
+                    }
                 }
             } else if s.contains("keyword=") || s.contains("key") || s.contains("kw") {
                 if !s.ends_with("=") {
-                    query.keyword = Some(s.split("=").collect::<Vec<&str>>()[1].to_string());
+                    let keyword = s.split("=").collect::<Vec<&str>>()[1].to_string();
+                    push_unique(&mut query.keywords, keyword);
                 }
+            } else if s.contains("user=") || s.contains("user_id=") {
+                if let Ok(user_id) = s.split("=").collect::<Vec<&str>>()[1].parse() {
+                    query.user_id = Some(user_id);
+                }
+            } else if s.contains("team=") || s.contains("team_id=") {
+                if let Ok(team_id) = s.split("=").collect::<Vec<&str>>()[1].parse() {
+                    query.team_id = Some(team_id);
+                }
+            } else if s.contains("letter=") {
+                query.letter = s.split("=").collect::<Vec<&str>>()[1].chars().next();
+            } else if s.contains("yanked=") {
+                query.include_yanked = parse_flag(s.split("=").collect::<Vec<&str>>()[1]);
+            } else if s.contains("following=") {
+                query.following =
+                    parse_flag(s.split("=").collect::<Vec<&str>>()[1]).unwrap_or(false);
             } else if s.contains("sort=") && !s.ends_with("=") {
                 query.sort = Sorting::from_str(s.split("=").collect::<Vec<&str>>()[1]);
             } else if s.contains("page=") && !s.ends_with("=") {
@@ -82,7 +154,23 @@ impl Query {
     }
 }
 
+fn push_unique<T: PartialEq>(items: &mut Vec<T>, item: T) {
+    if !items.contains(&item) {
+        items.push(item);
+    }
+}
+
+/// Parses a yes/no style flag value.
+fn parse_flag(input: &str) -> Option<bool> {
+    match input {
+        "yes" | "y" | "true" | "1" => Some(true),
+        "no" | "n" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
 /// Available sorting schemes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Sorting {
     Alphabetical,
     AllTimeDownloads,
@@ -102,6 +190,7 @@ impl Sorting {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &str) -> Option<Self> {
         let sort = match input {
             "alpha" | "alphabet" | "alphabetic" | "alphabetical" => Self::Alphabetical,
@@ -115,7 +204,34 @@ impl Sorting {
     }
 }
 
+/// Available sorting schemes for crate versions listings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionSorting {
+    Semver,
+    Date,
+}
+
+impl VersionSorting {
+    pub fn to_str(&self) -> &str {
+        match self {
+            VersionSorting::Semver => "semver",
+            VersionSorting::Date => "date",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &str) -> Option<Self> {
+        let sort = match input {
+            "semver" | "version" | "ver" => Self::Semver,
+            "date" | "time" | "released" | "newest" => Self::Date,
+            &_ => return None,
+        };
+        Some(sort)
+    }
+}
+
 /// Categories available on `crates.io`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Category {
     Accessibility,
     Algorithms,
@@ -229,6 +345,7 @@ impl Category {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &str) -> Option<Self> {
         let cat = match input {
             "accessibility" | "access" | "accessible" => Self::Accessibility,
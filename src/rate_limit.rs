@@ -0,0 +1,332 @@
+//! Rate limiting of requests performed by the client.
+
+use std::convert::TryFrom;
+use std::fs::TryLockError;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, TryLockError as MutexTryLockError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{Error, Result};
+
+/// Rate limit of one second is the smallest value tolerated by `crates.io`.
+pub const CRATES_IO_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest time a full burst may be spread over, keeping the limiter state
+/// well within the range of `Duration`.
+pub(crate) const MAX_WINDOW: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// Limits the rate at which the client performs requests.
+///
+/// Requests are spaced at least `interval` apart, with up to `burst`
+/// requests allowed to go through back-to-back after a period of inactivity.
+///
+/// When the client targets `crates.io` the limiter is never allowed to go
+/// faster than one request per second with no bursts. Clients configured
+/// otherwise are rejected by `ClientBuilder::build`. Custom interval, burst
+/// and disabling the limiter entirely are only possible with other
+/// registries, e.g. local mirrors.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use consecrates::{Client, RateLimiter};
+/// let client = Client::builder("my_app (me@me.com)")
+///     .base_url("http://localhost:8888/api/v1/")
+///     .rate_limiter(RateLimiter::new(Duration::from_millis(100)).burst(10))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// # Sharing
+///
+/// A single limiter can be shared by many clients by wrapping it in an `Arc`,
+/// in which case all of them collectively stay within the limits:
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use consecrates::{Client, RateLimiter};
+/// let limiter = Arc::new(RateLimiter::default());
+/// let client_a = Client::builder("my_app (me@me.com)")
+///     .rate_limiter(limiter.clone())
+///     .build()
+///     .unwrap();
+/// let client_b = Client::builder("my_app (me@me.com)")
+///     .rate_limiter(limiter)
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// To coordinate separate processes on one machine, e.g. multiple cron jobs,
+/// use a file backed limiter pointing all of them at the same file, see
+/// `RateLimiter::file_backed`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    burst: u32,
+    enabled: bool,
+    state: State,
+}
+
+/// Storage for the theoretical arrival time of the next request.
+#[derive(Debug)]
+enum State {
+    /// Kept in memory, as offset from the creation of the limiter
+    Local {
+        origin: Instant,
+        next: Mutex<Duration>,
+    },
+    /// Kept in a locked file, as offset from the unix epoch
+    File(PathBuf),
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(CRATES_IO_INTERVAL)
+    }
+}
+
+impl RateLimiter {
+    /// Creates a new limiter allowing one request per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            burst: 1,
+            enabled: true,
+            state: State::Local {
+                origin: Instant::now(),
+                next: Mutex::new(Duration::from_secs(0)),
+            },
+        }
+    }
+
+    /// Makes the limiter keep its state in the file at the given path,
+    /// coordinating with all other limiters using the same file, including
+    /// ones living in other processes.
+    ///
+    /// The file is created if it doesn't exist. Access is synchronized using
+    /// an exclusive file lock.
+    ///
+    /// ```rust
+    /// # use consecrates::{Client, RateLimiter};
+    /// let path = std::env::temp_dir().join("crates-io.ratelimit");
+    /// let client = Client::builder("my_app (me@me.com)")
+    ///     .rate_limiter(RateLimiter::default().file_backed(path))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn file_backed(mut self, path: impl AsRef<Path>) -> Self {
+        self.state = State::File(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Creates a new limiter that doesn't limit requests at all.
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the number of requests allowed to go through back-to-back.
+    ///
+    /// Values lower than `1` are treated as `1`.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Returns the minimum interval between requests.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the number of requests allowed to go through back-to-back.
+    pub fn burst_size(&self) -> u32 {
+        self.burst
+    }
+
+    /// Returns whether the limiter limits requests at all.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Tries to reserve a slot for a single request.
+    ///
+    /// Returns `Error::RateLimited` if the request is not allowed yet, or if
+    /// the limiter state is currently locked by someone else.
+    /// If `floor` is provided the limiter behaves as if it was configured to
+    /// allow no more than one request per `floor`.
+    pub(crate) fn try_acquire(&self, floor: Option<Duration>) -> Result<()> {
+        let (interval, burst) = match floor {
+            Some(floor) => (self.interval.max(floor), 1),
+            None if !self.enabled => return Ok(()),
+            None => (self.interval, self.burst),
+        };
+
+        let allowed = match &self.state {
+            State::Local { origin, next } => {
+                let mut next = match next.try_lock() {
+                    Ok(next) => next,
+                    Err(MutexTryLockError::WouldBlock) => return Err(Error::RateLimited),
+                    Err(MutexTryLockError::Poisoned(e)) => e.into_inner(),
+                };
+                reserve(&mut next, origin.elapsed(), interval, burst)
+            }
+            State::File(path) => {
+                let mut file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(path)?;
+                // lock is released when the file is closed
+                match file.try_lock() {
+                    Ok(()) => (),
+                    Err(TryLockError::WouldBlock) => return Err(Error::RateLimited),
+                    Err(TryLockError::Error(e)) => return Err(e.into()),
+                }
+                let mut next = read_next(&mut file)?;
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                let allowed = reserve(&mut next, now, interval, burst);
+                if allowed {
+                    write_next(&mut file, next)?;
+                }
+                allowed
+            }
+        };
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(Error::RateLimited)
+        }
+    }
+}
+
+/// Reserves a slot using the generic cell rate algorithm, with `next` and
+/// `now` measured from the same origin.
+///
+/// Arithmetic saturates, so huge intervals effectively mean "never again"
+/// rather than overflowing.
+fn reserve(next: &mut Duration, now: Duration, interval: Duration, burst: u32) -> bool {
+    let allowed_at = next.saturating_sub(interval.saturating_mul(burst - 1));
+    if now >= allowed_at {
+        *next = (*next).max(now).saturating_add(interval);
+        true
+    } else {
+        false
+    }
+}
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+fn read_next(file: &mut File) -> Result<Duration> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    // treat empty or garbled contents as no previous requests
+    let nanos = contents.trim().parse::<u128>().unwrap_or(0);
+    let secs = u64::try_from(nanos / NANOS_PER_SEC).unwrap_or(u64::MAX);
+    Ok(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+}
+
+fn write_next(file: &mut File, next: Duration) -> Result<()> {
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    write!(file, "{}", next.as_nanos())?;
+    Ok(())
+}
+
+/// Checks whether the given base url points at `crates.io`.
+pub(crate) fn targets_crates_io(base_url: &str) -> bool {
+    let without_scheme = base_url.split("://").last().unwrap_or(base_url);
+    let host = without_scheme
+        .split(['/', ':'])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    host == "crates.io" || host.ends_with(".crates.io")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn reserve_spaces_requests() {
+        let mut next = Duration::from_secs(0);
+        assert!(reserve(&mut next, Duration::from_secs(0), SEC, 1));
+        assert!(!reserve(&mut next, Duration::from_millis(999), SEC, 1));
+        assert!(reserve(&mut next, SEC, SEC, 1));
+        assert_eq!(next, 2 * SEC);
+    }
+
+    #[test]
+    fn reserve_allows_burst_after_idle() {
+        let mut next = Duration::from_secs(0);
+        let now = Duration::from_secs(10);
+        for _ in 0..3 {
+            assert!(reserve(&mut next, now, SEC, 3));
+        }
+        assert!(!reserve(&mut next, now, SEC, 3));
+        // one slot frees up per interval
+        assert!(reserve(&mut next, now + SEC, SEC, 3));
+        assert!(!reserve(&mut next, now + SEC, SEC, 3));
+    }
+
+    #[test]
+    fn huge_intervals_saturate() {
+        let mut next = Duration::from_secs(0);
+        assert!(reserve(&mut next, SEC, Duration::MAX, 1));
+        assert_eq!(next, Duration::MAX);
+        assert!(!reserve(&mut next, 2 * SEC, Duration::MAX, 1));
+
+        let limiter = RateLimiter::new(Duration::MAX);
+        assert!(limiter.try_acquire(None).is_ok());
+        assert!(matches!(limiter.try_acquire(None), Err(Error::RateLimited)));
+    }
+
+    #[test]
+    fn floor_overrides_configuration() {
+        let limiter = RateLimiter::new(Duration::from_millis(1)).burst(10);
+        assert!(limiter.try_acquire(Some(SEC)).is_ok());
+        assert!(matches!(
+            limiter.try_acquire(Some(SEC)),
+            Err(Error::RateLimited)
+        ));
+
+        let disabled = RateLimiter::disabled();
+        assert!(disabled.try_acquire(None).is_ok());
+        assert!(disabled.try_acquire(None).is_ok());
+        assert!(disabled.try_acquire(Some(SEC)).is_ok());
+        assert!(matches!(
+            disabled.try_acquire(Some(SEC)),
+            Err(Error::RateLimited)
+        ));
+    }
+
+    #[test]
+    fn file_state_is_shared() {
+        let path =
+            std::env::temp_dir().join(format!("consecrates-ratelimit-{}.test", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let interval = Duration::from_secs(3600);
+        let first = RateLimiter::new(interval).file_backed(&path);
+        let second = RateLimiter::new(interval).file_backed(&path);
+
+        assert!(first.try_acquire(None).is_ok());
+        assert!(matches!(second.try_acquire(None), Err(Error::RateLimited)));
+        assert!(matches!(first.try_acquire(None), Err(Error::RateLimited)));
+        std::fs::remove_file(&path).unwrap();
+
+        // slots beyond the range of u64 nanoseconds survive a round trip
+        let huge = RateLimiter::new(Duration::MAX).file_backed(&path);
+        assert!(huge.try_acquire(None).is_ok());
+        assert!(matches!(huge.try_acquire(None), Err(Error::RateLimited)));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
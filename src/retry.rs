@@ -0,0 +1,265 @@
+//! Retrying of failed requests.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+
+use crate::Error;
+
+/// Describes how the client retries requests that failed for transient
+/// reasons, such as connection failures, `429 Too Many Requests` or `5xx`
+/// responses.
+///
+/// Delays grow exponentially starting at `base_delay`, capped at
+/// `max_delay`. If the server provides a `Retry-After` header it's used
+/// instead of the computed delay.
+///
+/// Only the blocking `get_*` methods retry. The `try_get_*` methods never
+/// sleep and report failures right away.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use consecrates::{Client, RetryPolicy};
+/// let client = Client::builder("my_app (me@me.com)")
+///     .retry_policy(
+///         RetryPolicy::new(5)
+///             .base_delay(Duration::from_secs(2))
+///             .max_delay(Duration::from_secs(120)),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a new policy allowing for up to `max_attempts` attempts in
+    /// total, including the first one.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+
+    /// Creates a new policy that never retries.
+    pub fn never() -> Self {
+        Self::new(1)
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Sets the upper bound for delays between attempts.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Sets whether computed delays are randomized, which avoids many
+    /// clients retrying in lockstep. Enabled by default.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the maximum number of attempts, including the first one.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the delay to wait before the next attempt, or `None` if the
+    /// request shouldn't be retried.
+    ///
+    /// `attempt` is the number of attempts already made.
+    pub(crate) fn next_delay(&self, attempt: u32, error: &Error) -> Option<Duration> {
+        if attempt >= self.max_attempts || !is_transient(error) {
+            return None;
+        }
+        if let Some(retry_after) = error.retry_after() {
+            return Some(retry_after);
+        }
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        if self.jitter {
+            // keep at least half of the delay, randomize the rest
+            let half = delay / 2;
+            Some(half + half.mul_f64(random_fraction()))
+        } else {
+            Some(delay)
+        }
+    }
+}
+
+/// Checks whether the error is likely to go away if the request is repeated.
+fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::Transport(_) => true,
+        Error::Status { code, .. } | Error::Api { code, .. } => *code == 429 || *code >= 500,
+        _ => false,
+    }
+}
+
+/// Parses the value of a `Retry-After` header, given either in seconds or as
+/// a http date.
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let now: DateTime<Utc> = SystemTime::now().into();
+    Some(date.signed_duration_since(now).to_std().unwrap_or_default())
+}
+
+/// Returns a pseudo-random number in `[0, 1)`.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    fn server_error(retry_after: Option<Duration>) -> Error {
+        Error::Status {
+            code: 503,
+            snippet: String::new(),
+            retry_after,
+        }
+    }
+
+    #[test]
+    fn delay_grows_exponentially_up_to_max() {
+        let policy = RetryPolicy::new(10)
+            .base_delay(SEC)
+            .max_delay(5 * SEC)
+            .jitter(false);
+        let delays = (1..6)
+            .map(|attempt| policy.next_delay(attempt, &server_error(None)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            vec![
+                Some(SEC),
+                Some(2 * SEC),
+                Some(4 * SEC),
+                Some(5 * SEC),
+                Some(5 * SEC)
+            ]
+        );
+    }
+
+    #[test]
+    fn jitter_keeps_at_least_half_of_delay() {
+        let policy = RetryPolicy::new(10).base_delay(4 * SEC);
+        for _ in 0..20 {
+            let delay = policy.next_delay(1, &server_error(None)).unwrap();
+            assert!(delay >= 2 * SEC && delay <= 4 * SEC);
+        }
+    }
+
+    #[test]
+    fn retry_after_takes_priority() {
+        let policy = RetryPolicy::new(3).max_delay(SEC).jitter(false);
+        let error = server_error(Some(30 * SEC));
+        assert_eq!(policy.next_delay(1, &error), Some(30 * SEC));
+    }
+
+    #[test]
+    fn stops_after_max_attempts() {
+        let policy = RetryPolicy::new(3);
+        assert!(policy.next_delay(2, &server_error(None)).is_some());
+        assert_eq!(policy.next_delay(3, &server_error(None)), None);
+        assert_eq!(
+            RetryPolicy::never().next_delay(1, &server_error(None)),
+            None
+        );
+    }
+
+    #[test]
+    fn only_transient_errors_are_retried() {
+        let policy = RetryPolicy::new(3);
+        let transient = vec![
+            Error::transport("connection reset"),
+            Error::Status {
+                code: 429,
+                snippet: String::new(),
+                retry_after: None,
+            },
+            Error::Api {
+                code: 500,
+                errors: Vec::new(),
+                retry_after: None,
+            },
+        ];
+        for error in &transient {
+            assert!(policy.next_delay(1, error).is_some(), "{:?}", error);
+        }
+        let permanent = vec![
+            Error::Status {
+                code: 400,
+                snippet: String::new(),
+                retry_after: None,
+            },
+            Error::NotFound {
+                url: String::new(),
+                detail: None,
+            },
+            Error::MissingFixture {
+                url: String::new(),
+                path: Default::default(),
+            },
+            Error::InvalidQuery(String::new()),
+        ];
+        for error in &permanent {
+            assert_eq!(policy.next_delay(1, error), None, "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_dates() {
+        assert_eq!(parse_retry_after("120"), Some(120 * SEC));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::from_secs(0)));
+        // dates in the past mean no delay
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::from_secs(0))
+        );
+        let in_a_minute = (Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        let delay = parse_retry_after(&in_a_minute).unwrap();
+        assert!(delay > 55 * SEC && delay <= 60 * SEC);
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
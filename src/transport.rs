@@ -0,0 +1,372 @@
+//! Pluggable HTTP transport used by the client.
+//!
+//! By default the client talks to the registry using `http_req`. Any other
+//! HTTP stack can be plugged in by implementing the `Transport` trait and
+//! passing it to `ClientBuilder::transport`.
+//!
+//! `RecordingTransport` and `ReplayTransport` can be used together to capture
+//! real registry responses into a fixtures directory and later serve them
+//! back without touching the network, e.g. in tests.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use http_req::request::RedirectPolicy;
+use http_req::uri::Uri;
+
+use crate::auth::REDACTED;
+use crate::{Error, Result};
+
+/// Single outgoing request handed over to a transport.
+///
+/// The `Debug` output leaves out the value of the `Authorization` header.
+#[derive(Clone)]
+pub struct Request {
+    /// Full target url
+    pub url: String,
+    /// Headers to send along with the request
+    pub headers: Vec<(String, String)>,
+    /// Timeouts to apply to the request
+    pub timeouts: Timeouts,
+}
+
+impl fmt::Debug for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(key, value)| {
+                if key.eq_ignore_ascii_case("authorization") {
+                    (key.as_str(), REDACTED)
+                } else {
+                    (key.as_str(), value.as_str())
+                }
+            })
+            .collect::<Vec<_>>();
+        f.debug_struct("Request")
+            .field("url", &self.url)
+            .field("headers", &headers)
+            .field("timeouts", &self.timeouts)
+            .finish()
+    }
+}
+
+/// Timeouts applied to a single request, `None` means waiting indefinitely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Timeout for establishing a connection
+    pub connect: Option<Duration>,
+    /// Timeout for reading the response
+    pub read: Option<Duration>,
+    /// Timeout for sending the request
+    pub write: Option<Duration>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Some(Duration::from_secs(10)),
+            read: Some(Duration::from_secs(30)),
+            write: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl Request {
+    /// Returns the value of the first header with the given name, compared
+    /// case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+/// Raw response returned by a transport.
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// HTTP status code
+    pub status: u16,
+    /// Response headers
+    pub headers: Vec<(String, String)>,
+    /// Raw response body
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Returns the value of the first header with the given name, compared
+    /// case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Backend capable of performing HTTP GET requests.
+pub trait Transport: Send + Sync {
+    /// Performs the request, returning the status, headers and body of the
+    /// response.
+    ///
+    /// Non-success status codes are not considered errors at this level.
+    /// Failures should be reported using `Error::Transport`, see
+    /// `Error::transport`.
+    fn send(&self, request: &Request) -> Result<Response>;
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn send(&self, request: &Request) -> Result<Response> {
+        (**self).send(request)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send(&self, request: &Request) -> Result<Response> {
+        (**self).send(request)
+    }
+}
+
+/// Maximum number of redirects followed by `HttpReqTransport`.
+const MAX_REDIRECTS: usize = 5;
+
+/// Default transport based on the `http_req` crate.
+///
+/// Redirects are followed with the headers and timeouts of the original
+/// request, except for the `Authorization` header which is only sent to the
+/// origin it was meant for.
+#[derive(Debug, Clone, Default)]
+pub struct HttpReqTransport;
+
+impl Transport for HttpReqTransport {
+    fn send(&self, request: &Request) -> Result<Response> {
+        let mut url = request.url.clone();
+        let mut headers = request.headers.clone();
+        for _ in 0..=MAX_REDIRECTS {
+            let response = send_once(&url, &headers, &request.timeouts)?;
+            let location = match response.header("Location") {
+                Some(location) if (300..400).contains(&response.status) => location,
+                _ => return Ok(response),
+            };
+            let next_url = resolve_location(&url, location);
+            if !origin(&next_url).eq_ignore_ascii_case(origin(&url)) {
+                headers.retain(|(key, _)| !key.eq_ignore_ascii_case("authorization"));
+            }
+            url = next_url;
+        }
+        Err(Error::transport(format!(
+            "too many redirects for url {}",
+            request.url
+        )))
+    }
+}
+
+/// Performs a single request without following redirects.
+fn send_once(url: &str, headers: &[(String, String)], timeouts: &Timeouts) -> Result<Response> {
+    let mut body = Vec::new();
+    let uri = Uri::try_from(url).map_err(Error::transport)?;
+    let mut req = http_req::request::Request::new(&uri);
+    for (key, value) in headers {
+        req.header(key, value);
+    }
+    // http_req drops headers and timeouts when following redirects itself
+    req.redirect_policy(RedirectPolicy::Limit(0))
+        .connect_timeout(timeouts.connect)
+        .read_timeout(timeouts.read)
+        .write_timeout(timeouts.write);
+    let response = req.send(&mut body).map_err(Error::transport)?;
+
+    Ok(Response {
+        status: response.status_code().into(),
+        headers: response
+            .headers()
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        body,
+    })
+}
+
+/// Returns the scheme and authority part of the url, e.g.
+/// `https://crates.io` for `https://crates.io/api/v1/crates`.
+fn origin(url: &str) -> &str {
+    let authority_start = url.find("://").map(|i| i + 3).unwrap_or(0);
+    let end = url[authority_start..]
+        .find(['/', '?', '#'])
+        .map(|i| authority_start + i)
+        .unwrap_or(url.len());
+    &url[..end]
+}
+
+/// Resolves the value of a `Location` header against the url it was
+/// returned for.
+fn resolve_location(base: &str, location: &str) -> String {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        location.to_string()
+    } else if let Some(rest) = location.strip_prefix("//") {
+        let scheme = base.split("://").next().unwrap_or("https");
+        format!("{}://{}", scheme, rest)
+    } else if location.starts_with('/') {
+        format!("{}{}", origin(base), location)
+    } else {
+        let path = base.split(['?', '#']).next().unwrap_or(base);
+        let dir = match path.rfind('/') {
+            Some(i) if i >= origin(base).len() => &path[..=i],
+            _ => return format!("{}/{}", origin(base), location),
+        };
+        format!("{}{}", dir, location)
+    }
+}
+
+/// Transport that saves every response to a fixtures directory.
+///
+/// Each request produces two files named after the url (see `fixture_name`):
+/// `<name>.body` holding the raw response body and `<name>.meta.json`
+/// holding the url, status and headers of the response.
+pub struct RecordingTransport {
+    dir: PathBuf,
+    inner: Box<dyn Transport>,
+}
+
+impl RecordingTransport {
+    /// Creates a new recording transport performing requests with the
+    /// default `HttpReqTransport`.
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self::wrap(dir, HttpReqTransport)
+    }
+
+    /// Creates a new recording transport performing requests with the given
+    /// transport.
+    pub fn wrap(dir: impl AsRef<Path>, inner: impl Transport + 'static) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            inner: Box::new(inner),
+        }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn send(&self, request: &Request) -> Result<Response> {
+        let response = self.inner.send(request)?;
+
+        fs::create_dir_all(&self.dir)?;
+        let name = fixture_name(&request.url);
+        let meta = FixtureMeta {
+            url: request.url.clone(),
+            status: response.status,
+            headers: response.headers.clone(),
+        };
+        fs::write(
+            self.dir.join(format!("{}.meta.json", name)),
+            serde_json::to_vec_pretty(&meta).map_err(Error::transport)?,
+        )?;
+        fs::write(self.dir.join(format!("{}.body", name)), &response.body)?;
+
+        Ok(response)
+    }
+}
+
+/// Transport serving responses previously saved by `RecordingTransport`.
+///
+/// Never touches the network. Requests for which no fixture exists fail
+/// with `Error::MissingFixture`, which is never retried. The `.meta.json`
+/// file is optional, when missing the response is served with status `200`
+/// and no headers, which makes writing fixtures by hand easier.
+pub struct ReplayTransport {
+    dir: PathBuf,
+}
+
+impl ReplayTransport {
+    /// Creates a new replay transport serving fixtures from the given
+    /// directory.
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn send(&self, request: &Request) -> Result<Response> {
+        let name = fixture_name(&request.url);
+        let body_path = self.dir.join(format!("{}.body", name));
+        if !body_path.is_file() {
+            return Err(Error::MissingFixture {
+                url: request.url.clone(),
+                path: body_path,
+            });
+        }
+        let body = fs::read(&body_path)?;
+
+        let meta_path = self.dir.join(format!("{}.meta.json", name));
+        let (status, headers) = if meta_path.is_file() {
+            let bytes = fs::read(&meta_path)?;
+            let meta: FixtureMeta =
+                serde_json::from_slice(&bytes).map_err(|e| Error::json(e, &bytes))?;
+            (meta.status, meta.headers)
+        } else {
+            (200, Vec::new())
+        };
+
+        Ok(Response {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FixtureMeta {
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+}
+
+/// Maximum length of a fixture name before it gets shortened.
+const MAX_FIXTURE_NAME_LEN: usize = 120;
+
+/// Returns the file name (without extension) under which the response for
+/// the given url is stored.
+///
+/// The scheme is dropped and every character other than ascii alphanumerics,
+/// `.` and `-` is replaced with `_`, e.g. `https://crates.io/api/v1/crates/serde`
+/// becomes `crates.io_api_v1_crates_serde`. Overly long names are truncated
+/// and suffixed with a hash of the full url.
+pub fn fixture_name(url: &str) -> String {
+    let stripped = url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    let mut name: String = stripped
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.len() > MAX_FIXTURE_NAME_LEN {
+        name.truncate(MAX_FIXTURE_NAME_LEN);
+        name.push_str(&format!("_{:016x}", fnv1a(url.as_bytes())));
+    }
+    name
+}
+
+/// Stable 64-bit FNV-1a hash, used so that fixture names don't change between
+/// runs or compiler versions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
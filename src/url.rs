@@ -0,0 +1,75 @@
+//! Url building with percent-encoding of path segments and query values.
+
+/// Incrementally builds a request url on top of the client's base url.
+pub(crate) struct UrlBuilder {
+    url: String,
+    has_query: bool,
+}
+
+impl UrlBuilder {
+    /// Starts a new url at the given base.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            url: base_url.to_string(),
+            has_query: false,
+        }
+    }
+
+    /// Appends a single path segment, percent-encoding it.
+    pub fn path(mut self, segment: &str) -> Self {
+        debug_assert!(!self.has_query, "path segment added after query params");
+        if !self.url.ends_with('/') {
+            self.url.push('/');
+        }
+        encode_into(&mut self.url, segment);
+        self
+    }
+
+    /// Appends a query parameter, percent-encoding both key and value.
+    pub fn param(mut self, key: &str, value: &str) -> Self {
+        self.url.push(if self.has_query { '&' } else { '?' });
+        self.has_query = true;
+        encode_into(&mut self.url, key);
+        self.url.push('=');
+        encode_into(&mut self.url, value);
+        self
+    }
+
+    /// Appends a query parameter if the value is present.
+    pub fn opt_param<T: ToString>(self, key: &str, value: Option<T>) -> Self {
+        match value {
+            Some(value) => self.param(key, &value.to_string()),
+            None => self,
+        }
+    }
+
+    /// Appends an already encoded query string verbatim, e.g. one provided
+    /// by the server. A leading `?` is optional.
+    pub fn raw_query(mut self, query: &str) -> Self {
+        let query = query.trim_start_matches('?');
+        if !query.is_empty() {
+            self.url.push(if self.has_query { '&' } else { '?' });
+            self.has_query = true;
+            self.url.push_str(query);
+        }
+        self
+    }
+
+    /// Returns the finished url.
+    pub fn build(self) -> String {
+        self.url
+    }
+}
+
+/// Percent-encodes everything except unreserved characters as defined by
+/// RFC 3986.
+fn encode_into(out: &mut String, input: &str) {
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Checks how the api token is sent and kept out of debug output.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{Mock, USER_AGENT};
+use consecrates::{Client, Token};
+
+const ME: &str = r#"{"user":{"avatar":null,"email":null,"id":7,"kind":null,"login":"me","name":null,"url":"https://github.com/me"},"owned_crates":[{"id":1,"name":"mine","email_notifications":true}]}"#;
+
+#[test]
+fn token_is_sent_but_not_printed() {
+    let mock = Arc::new(Mock::new(200, ME));
+    let client = Client::builder(USER_AGENT)
+        .token(Token::new("s3cr3t"))
+        .transport(mock.clone())
+        .build()
+        .unwrap();
+    let me = client.get_me().unwrap();
+    assert_eq!(me.user.login, "me");
+    assert_eq!(me.owned_crates[0].name, "mine");
+
+    let requests = mock.requests();
+    assert_eq!(requests[0].url, "https://crates.io/api/v1/me");
+    assert_eq!(requests[0].header("authorization"), Some("s3cr3t"));
+    assert!(!format!("{:?}", requests[0]).contains("s3cr3t"));
+    assert!(!format!("{:?} {}", Token::new("s3cr3t"), Token::new("s3cr3t")).contains("s3cr3t"));
+}
+
+#[test]
+fn authorization_header_is_rejected() {
+    let result = Client::builder(USER_AGENT)
+        .header("Authorization", "s3cr3t")
+        .build();
+    assert!(result.is_err());
+}
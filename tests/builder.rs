@@ -0,0 +1,97 @@
+//! Checks which client configurations are rejected by the builder.
+
+mod common;
+
+use std::time::Duration;
+
+use common::{MIRROR_URL, USER_AGENT};
+use consecrates::{Client, ClientBuilder, Error, RateLimiter, RetryPolicy};
+
+fn assert_invalid(builder: ClientBuilder) {
+    match builder.build() {
+        Err(Error::InvalidConfig(_)) => (),
+        Err(e) => panic!("expected invalid config, got {}", e),
+        Ok(_) => panic!("expected invalid config, got a client"),
+    }
+}
+
+#[test]
+fn user_agent_format_is_checked_for_crates_io_only() {
+    assert_invalid(Client::builder("my_tool"));
+    assert_invalid(Client::builder("my_tool ()"));
+    assert_invalid(Client::builder("my_tool (nobody)"));
+    assert!(Client::builder("my_tool (me@me.com)").build().is_ok());
+    assert!(Client::builder("my_tool")
+        .base_url(MIRROR_URL)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn old_constructors_never_panic() {
+    Client::new("my_tool");
+    Client::new_with_base_url("not a url", "");
+}
+
+#[test]
+fn invalid_base_url_is_rejected() {
+    assert_invalid(Client::builder(USER_AGENT).base_url("ftp://registry.test/"));
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    assert_invalid(Client::builder(USER_AGENT).header("X-Test", "a\r\nInjected: 1"));
+    assert_invalid(Client::builder(USER_AGENT).header("X Test", "a"));
+    assert_invalid(Client::builder(USER_AGENT).header("user-agent", "other"));
+    assert!(Client::builder(USER_AGENT)
+        .header("X-Test", "a")
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn zero_durations_are_rejected() {
+    let zero = Some(Duration::from_secs(0));
+    assert_invalid(Client::builder(USER_AGENT).connect_timeout(zero));
+    assert_invalid(Client::builder(USER_AGENT).read_timeout(zero));
+    assert_invalid(Client::builder(USER_AGENT).write_timeout(zero));
+    assert_invalid(Client::builder(USER_AGENT).acquire_timeout(zero));
+    assert_invalid(Client::builder(USER_AGENT).cache(Duration::from_secs(0)));
+    assert_invalid(Client::builder(USER_AGENT).retry_policy(RetryPolicy::new(0)));
+    assert!(Client::builder(USER_AGENT)
+        .read_timeout(None)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn crates_io_rate_limit_is_enforced() {
+    assert_invalid(Client::builder(USER_AGENT).rate_limiter(RateLimiter::disabled()));
+    assert_invalid(
+        Client::builder(USER_AGENT).rate_limiter(RateLimiter::new(Duration::from_millis(500))),
+    );
+    assert_invalid(Client::builder(USER_AGENT).rate_limiter(RateLimiter::default().burst(2)));
+    assert!(Client::builder(USER_AGENT)
+        .rate_limiter(RateLimiter::new(Duration::from_secs(2)))
+        .build()
+        .is_ok());
+    assert!(Client::builder(USER_AGENT)
+        .base_url(MIRROR_URL)
+        .rate_limiter(RateLimiter::disabled())
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn overflowing_rate_limiter_is_rejected() {
+    assert_invalid(
+        Client::builder(USER_AGENT)
+            .base_url(MIRROR_URL)
+            .rate_limiter(RateLimiter::new(Duration::MAX).burst(2)),
+    );
+    assert!(Client::builder(USER_AGENT)
+        .base_url(MIRROR_URL)
+        .rate_limiter(RateLimiter::new(Duration::from_secs(3600)).burst(24))
+        .build()
+        .is_ok());
+}
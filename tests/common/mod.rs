@@ -0,0 +1,148 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use consecrates::transport::{Request, Response};
+use consecrates::{Client, RateLimiter, Result, RetryPolicy, Transport};
+
+/// User agent used by all test clients.
+pub const USER_AGENT: &str = "consecrates_tests (github.com/adamsky/consecrates)";
+
+/// Base url of a registry that isn't `crates.io`, so that the rate limiter
+/// can be disabled.
+pub const MIRROR_URL: &str = "http://registry.test/api/v1/";
+
+/// Listing page with no crates.
+pub const EMPTY_CRATES: &str =
+    r#"{"crates":[],"meta":{"total":0,"next_page":null,"prev_page":null}}"#;
+
+/// Transport remembering requests and answering with canned responses,
+/// picked by exact url or falling back to a default one.
+///
+/// Routes added repeatedly for the same url are served in order, the last
+/// one answering all further requests.
+pub struct Mock {
+    requests: Mutex<Vec<Request>>,
+    routes: Vec<(String, Response)>,
+    fallback: Response,
+}
+
+impl Mock {
+    /// Creates a mock answering every request with the given response.
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            requests: Mutex::new(Vec::new()),
+            routes: Vec::new(),
+            fallback: response(status, body),
+        }
+    }
+
+    /// Answers requests for the given url with the given response.
+    pub fn route(mut self, url: &str, status: u16, body: &str) -> Self {
+        self.routes.push((url.to_string(), response(status, body)));
+        self
+    }
+
+    /// Adds a header to the most recently added response, the default one if
+    /// no routes were added yet.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        let response = match self.routes.last_mut() {
+            Some((_, response)) => response,
+            None => &mut self.fallback,
+        };
+        response.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Returns all requests made so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns urls of all requests made so far.
+    pub fn urls(&self) -> Vec<String> {
+        self.requests().into_iter().map(|r| r.url).collect()
+    }
+}
+
+impl Transport for Mock {
+    fn send(&self, request: &Request) -> Result<Response> {
+        let mut requests = self.requests.lock().unwrap();
+        let seen = requests.iter().filter(|r| r.url == request.url).count();
+        requests.push(request.clone());
+        let routes = self
+            .routes
+            .iter()
+            .filter(|(url, _)| *url == request.url)
+            .map(|(_, response)| response)
+            .collect::<Vec<_>>();
+        let response = routes
+            .get(seen)
+            .or_else(|| routes.last())
+            .copied()
+            .unwrap_or(&self.fallback);
+        Ok(response.clone())
+    }
+}
+
+fn response(status: u16, body: &str) -> Response {
+    Response {
+        status,
+        headers: Vec::new(),
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+/// Creates a client talking to `MIRROR_URL` through the given mock, with
+/// rate limiting and retries disabled so that tests run fast.
+pub fn mirror_client(mock: &Arc<Mock>) -> Client {
+    Client::builder(USER_AGENT)
+        .base_url(MIRROR_URL)
+        .rate_limiter(RateLimiter::disabled())
+        .retry_policy(RetryPolicy::never())
+        .transport(mock.clone())
+        .build()
+        .unwrap()
+}
+
+/// Returns a crate listing page with minimal crates of the given names.
+pub fn crates_page(names: &[&str], total: u64, next_page: Option<&str>) -> String {
+    let crates = names
+        .iter()
+        .map(|name| {
+            serde_json::json!({
+                "id": name,
+                "name": name,
+                "description": null,
+                "license": null,
+                "documentation": null,
+                "homepage": null,
+                "repository": null,
+                "downloads": 0,
+                "recent_downloads": null,
+                "categories": null,
+                "keywords": null,
+                "versions": null,
+                "max_version": "1.0.0",
+                "links": {
+                    "owner_team": "",
+                    "owner_user": "",
+                    "owners": "",
+                    "reverse_dependencies": "",
+                    "version_downloads": "",
+                    "versions": null
+                },
+                "created_at": "2020-01-01T00:00:00Z",
+                "updated_at": "2020-01-01T00:00:00Z",
+                "exact_match": null
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "crates": crates,
+        "meta": { "total": total, "next_page": next_page, "prev_page": null }
+    })
+    .to_string()
+}
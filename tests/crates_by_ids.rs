@@ -0,0 +1,36 @@
+//! Checks looking up many crates by name at once.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{crates_page, mirror_client, Mock};
+
+#[test]
+fn looks_up_names_in_chunks() {
+    let mock = Arc::new(Mock::new(200, &crates_page(&["serde_json"], 1, None)));
+    let mut ids = vec!["serde-json".to_string(), "no-such-crate".to_string()];
+    ids.extend((0..99).map(|i| format!("crate-{}", i)));
+    let ids = ids.iter().map(|id| id.as_str()).collect::<Vec<_>>();
+    assert_eq!(ids.len(), 101);
+
+    let crates = mirror_client(&mock).get_crates_by_ids(&ids).unwrap();
+
+    let urls = mock.urls();
+    assert_eq!(urls.len(), 2);
+    assert!(urls[0].starts_with(
+        "http://registry.test/api/v1/crates?ids%5B%5D=serde-json&ids%5B%5D=no-such-crate&"
+    ));
+    assert!(urls[0].ends_with("&ids%5B%5D=crate-97&per_page=100"));
+    assert_eq!(
+        urls[1],
+        "http://registry.test/api/v1/crates?ids%5B%5D=crate-98&per_page=1"
+    );
+
+    // names are matched regardless of dashes and underscores, keyed as given
+    assert_eq!(crates.found.len(), 1);
+    assert_eq!(crates.found["serde-json"].name, "serde_json");
+    assert_eq!(crates.missing.len(), 100);
+    assert_eq!(crates.missing[0], "no-such-crate");
+    assert!(!crates.missing.contains(&"serde-json".to_string()));
+}
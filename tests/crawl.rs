@@ -0,0 +1,88 @@
+//! Checks resuming crawls from checkpoint files.
+
+mod common;
+
+use std::fs;
+use std::sync::Arc;
+
+use common::{crates_page, mirror_client, Mock};
+
+const FIRST: &str = "http://registry.test/api/v1/crates?per_page=100&sort=alpha";
+
+fn mock() -> Mock {
+    Mock::new(500, "unexpected request")
+        .route(FIRST, 200, &crates_page(&["a"], 3, Some("?seek=a")))
+        .route(
+            "http://registry.test/api/v1/crates?seek=a",
+            200,
+            &crates_page(&["b"], 3, Some("?seek=b")),
+        )
+        .route(
+            "http://registry.test/api/v1/crates?seek=b",
+            200,
+            &crates_page(&["c"], 3, None),
+        )
+}
+
+fn checkpoint_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!(
+        "consecrates-{}-{}.checkpoint",
+        name,
+        std::process::id()
+    ))
+}
+
+fn crawl(path: &std::path::Path) -> (Vec<String>, Vec<String>) {
+    let mock = Arc::new(mock());
+    let crates = mirror_client(&mock)
+        .crawler(path)
+        .run(|_| ())
+        .unwrap()
+        .into_iter()
+        .map(|c| c.name)
+        .collect();
+    (crates, mock.urls())
+}
+
+#[test]
+fn crawls_whole_listing() {
+    let path = checkpoint_path("whole");
+    let _ = fs::remove_file(&path);
+
+    let (crates, urls) = crawl(&path);
+    assert_eq!(crates, vec!["a", "b", "c"]);
+    assert_eq!(urls.len(), 3);
+
+    // a finished checkpoint is served without any requests
+    let (crates, urls) = crawl(&path);
+    assert_eq!(crates, vec!["a", "b", "c"]);
+    assert!(urls.is_empty());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn resumes_twice_after_partial_write() {
+    let path = checkpoint_path("partial");
+    let _ = fs::remove_file(&path);
+    let first_page: serde_json::Value =
+        serde_json::from_str(&crates_page(&["a"], 3, Some("?seek=a"))).unwrap();
+    let line = serde_json::json!({
+        "next_page": "?seek=a",
+        "total": 3,
+        "crates": first_page["crates"],
+    });
+    fs::write(&path, format!("{}\n{{\"next_page\":nu", line)).unwrap();
+
+    let (crates, urls) = crawl(&path);
+    assert_eq!(crates, vec!["a", "b", "c"]);
+    assert_eq!(urls.len(), 2);
+
+    let contents = fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.lines().count(), 3);
+    assert!(!contents.contains("nu{"));
+
+    let (crates, urls) = crawl(&path);
+    assert_eq!(crates, vec!["a", "b", "c"]);
+    assert!(urls.is_empty());
+    fs::remove_file(&path).unwrap();
+}
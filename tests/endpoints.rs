@@ -0,0 +1,114 @@
+//! Endpoint tests served from recorded fixtures, without touching the network.
+
+use consecrates::{Client, Error, Query, ReplayTransport};
+
+fn client() -> Client {
+    Client::builder("consecrates_tests (github.com/adamsky/consecrates)")
+        .transport(ReplayTransport::new("tests/fixtures"))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn crate_version() {
+    let version = client().get_crate_version("serde", "1.0.0").unwrap();
+    assert_eq!(version.crate_name, "serde");
+    assert_eq!(version.num, "1.0.0");
+    assert_eq!(version.features["derive"], vec!["serde_derive"]);
+}
+
+#[test]
+fn crate_authors() {
+    let authors = client().get_crate_authors("serde", "1.0.0").unwrap();
+    assert_eq!(authors.names.len(), 2);
+    assert!(authors.names[1].starts_with("David Tolnay"));
+    assert!(authors.users.is_empty());
+}
+
+#[test]
+fn crate_readme() {
+    let readme = client().get_crate_readme("serde", "1.0.0").unwrap();
+    assert!(readme.starts_with("<h1>Serde</h1>"));
+}
+
+#[test]
+fn version_downloads() {
+    let downloads = client().get_version_downloads("serde", "1.0.0").unwrap();
+    assert_eq!(downloads.len(), 3);
+    assert!(downloads.iter().all(|d| d.version == 52935));
+    assert_eq!(downloads[0].date.to_string(), "2021-03-02");
+    assert_eq!(downloads.iter().map(|d| d.downloads).sum::<u64>(), 44);
+}
+
+#[test]
+fn user() {
+    let user = client().get_user("dtolnay").unwrap();
+    assert_eq!(user.id, 3618);
+    assert_eq!(user.login, "dtolnay");
+    assert_eq!(user.name.as_deref(), Some("David Tolnay"));
+    assert_eq!(user.email, None);
+}
+
+#[test]
+fn team() {
+    let team = client().get_team("github:rust-lang:libs").unwrap();
+    assert_eq!(team.id, 16);
+    assert_eq!(team.login, "github:rust-lang:libs");
+    assert_eq!(team.github_org(), Some("rust-lang"));
+}
+
+#[test]
+fn crate_owner_teams() {
+    let owners = client().get_crate_owner_teams("libc").unwrap();
+    assert_eq!(owners.teams.len(), 1);
+    assert_eq!(owners.teams[0].login, "github:rust-lang:libs");
+    assert_eq!(owners.teams[0].github_team(), Some("libs"));
+}
+
+#[test]
+fn reverse_dependencies_pair_by_version_id() {
+    let query = Query {
+        per_page: Some(2),
+        ..Default::default()
+    };
+    let pairs = client()
+        .get_crate_reverse_dependencies("serde_derive", query)
+        .unwrap()
+        .into_pairs();
+    let dependents = pairs
+        .iter()
+        .map(|pair| {
+            let version = pair.version.as_ref().unwrap();
+            assert_eq!(version.id, pair.dependency.version_id);
+            (version.crate_name.as_str(), pair.dependency.req.as_str())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        dependents,
+        vec![("toml", "^1.0"), ("serde_json", "^1.0.100")]
+    );
+}
+
+#[test]
+fn reverse_dependencies_iter_numbers_pages() {
+    let query = Query {
+        per_page: Some(2),
+        ..Default::default()
+    };
+    let client = client();
+    let dependents = client
+        .reverse_dependencies_iter("serde_derive", query)
+        .map(|pair| pair.unwrap().version.unwrap().crate_name)
+        .collect::<Vec<_>>();
+    // the second page is only served for `page=2`
+    assert_eq!(dependents, vec!["toml", "serde_json", "serde"]);
+}
+
+#[test]
+fn missing_fixture_names_url() {
+    let error = client().get_crate_version("serde", "0.0.0").unwrap_err();
+    assert!(matches!(error, Error::MissingFixture { .. }));
+    assert!(error
+        .to_string()
+        .contains("https://crates.io/api/v1/crates/serde/0.0.0"));
+}
@@ -0,0 +1,250 @@
+//! Checks how paged listings are walked by the iterators.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{crates_page, mirror_client, Mock};
+use consecrates::{Category, Error, Query, VersionSorting};
+
+const FIRST: &str = "http://registry.test/api/v1/crates?per_page=2";
+const SECOND: &str = "http://registry.test/api/v1/crates?seek=b&per_page=2";
+const THIRD: &str = "http://registry.test/api/v1/crates?seek=d&per_page=2";
+
+fn query() -> Query {
+    Query {
+        per_page: Some(2),
+        ..Default::default()
+    }
+}
+
+fn names(mock: &Arc<Mock>, max_items: Option<u64>) -> Vec<String> {
+    let client = mirror_client(mock);
+    let iter = client.crates_iter(query());
+    let iter = match max_items {
+        Some(max_items) => iter.max_items(max_items),
+        None => iter,
+    };
+    iter.map(|c| c.unwrap().name).collect()
+}
+
+/// Returns a versions page of the `foo` crate, without `prev_page` in meta.
+fn versions_page(nums: &[&str], total: u64, next_page: Option<&str>) -> String {
+    let versions = nums
+        .iter()
+        .enumerate()
+        .map(|(id, num)| {
+            serde_json::json!({
+                "crate": "foo",
+                "created_at": "2020-01-01T00:00:00Z",
+                "updated_at": "2020-01-01T00:00:00Z",
+                "dl_path": "",
+                "downloads": 0,
+                "features": {},
+                "id": id,
+                "num": num,
+                "yanked": false,
+                "license": null,
+                "readme_path": null,
+                "links": { "authors": "", "dependencies": "", "version_downloads": "" },
+                "crate_size": null,
+                "published_by": null
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "versions": versions,
+        "meta": { "total": total, "next_page": next_page }
+    })
+    .to_string()
+}
+
+fn three_pages() -> Mock {
+    Mock::new(500, "unexpected request")
+        .route(
+            FIRST,
+            200,
+            &crates_page(&["a", "b"], 5, Some("?seek=b&per_page=2")),
+        )
+        .route(
+            SECOND,
+            200,
+            &crates_page(&["c", "d"], 5, Some("?seek=d&per_page=2")),
+        )
+        .route(THIRD, 200, &crates_page(&["e"], 5, None))
+}
+
+#[test]
+fn follows_next_page_cursors() {
+    let mock = Arc::new(three_pages());
+    assert_eq!(names(&mock, None), vec!["a", "b", "c", "d", "e"]);
+    assert_eq!(mock.urls(), vec![FIRST, SECOND, THIRD]);
+}
+
+#[test]
+fn stops_at_total() {
+    let mock = Arc::new(Mock::new(500, "unexpected request").route(
+        FIRST,
+        200,
+        &crates_page(&["a", "b"], 2, Some("?seek=b&per_page=2")),
+    ));
+    assert_eq!(names(&mock, None), vec!["a", "b"]);
+    assert_eq!(mock.urls(), vec![FIRST]);
+}
+
+#[test]
+fn missing_link_falls_back_to_page_numbers() {
+    let second = "http://registry.test/api/v1/crates?page=2&per_page=2";
+    let mock = Arc::new(
+        Mock::new(500, "unexpected request")
+            .route(FIRST, 200, &crates_page(&["a", "b"], 4, None))
+            .route(second, 200, &crates_page(&["c", "d"], 4, None)),
+    );
+    assert_eq!(names(&mock, None), vec!["a", "b", "c", "d"]);
+    assert_eq!(mock.urls(), vec![FIRST, second]);
+}
+
+#[test]
+fn continues_past_pages_emptied_by_filtering() {
+    let first = "http://registry.test/api/v1/crates?per_page=2&category=asynchronous";
+    let second = "http://registry.test/api/v1/crates?seek=b&per_page=2&category=asynchronous";
+    let mock = Arc::new(
+        Mock::new(500, "unexpected request")
+            .route(
+                first,
+                200,
+                &crates_page(
+                    &["a", "b"],
+                    4,
+                    Some("?seek=b&per_page=2&category=asynchronous"),
+                ),
+            )
+            .route(
+                "http://registry.test/api/v1/crates?ids%5B%5D=a&ids%5B%5D=b\
+                 &category=network-programming&per_page=2",
+                200,
+                &crates_page(&[], 0, None),
+            )
+            .route(second, 200, &crates_page(&["c", "d"], 4, None))
+            .route(
+                "http://registry.test/api/v1/crates?ids%5B%5D=c&ids%5B%5D=d\
+                 &category=network-programming&per_page=2",
+                200,
+                &crates_page(&["d"], 1, None),
+            ),
+    );
+    let query = Query {
+        categories: vec![Category::Asynchronous, Category::NetworkProgramming],
+        ..query()
+    };
+    let client = mirror_client(&mock);
+    let names = client
+        .crates_iter(query)
+        .map(|c| c.unwrap().name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["d"]);
+    assert_eq!(mock.urls().len(), 4);
+    assert_eq!(mock.urls()[2], second);
+}
+
+#[test]
+fn unlinked_pages_are_numbered() {
+    let category = |slug: &str| {
+        format!(
+            r#"{{"category":"{0}","crates_cnt":1,"created_at":"2020-01-01T00:00:00Z","description":"","id":"{0}","slug":"{0}"}}"#,
+            slug
+        )
+    };
+    let mock = Arc::new(
+        Mock::new(500, "unexpected request")
+            .route(
+                "http://registry.test/api/v1/categories?per_page=2",
+                200,
+                &format!(
+                    r#"{{"categories":[{},{}],"meta":{{"total":3}}}}"#,
+                    category("a"),
+                    category("b")
+                ),
+            )
+            .route(
+                "http://registry.test/api/v1/categories?page=2&per_page=2",
+                200,
+                &format!(
+                    r#"{{"categories":[{}],"meta":{{"total":3}}}}"#,
+                    category("c")
+                ),
+            ),
+    );
+    let client = mirror_client(&mock);
+    let slugs = client
+        .categories_iter(query())
+        .map(|c| c.unwrap().slug)
+        .collect::<Vec<_>>();
+    assert_eq!(slugs, vec!["a", "b", "c"]);
+    assert_eq!(mock.urls().len(), 2);
+}
+
+#[test]
+fn max_items_limits_requests() {
+    let mock = Arc::new(three_pages());
+    assert_eq!(names(&mock, Some(3)), vec!["a", "b", "c"]);
+    assert_eq!(mock.urls(), vec![FIRST, SECOND]);
+}
+
+#[test]
+fn ends_after_first_error() {
+    let mock = Arc::new(Mock::new(500, "unexpected request").route(
+        FIRST,
+        200,
+        &crates_page(&["a", "b"], 5, Some("?seek=b&per_page=2")),
+    ));
+    let client = mirror_client(&mock);
+    let mut iter = client.crates_iter(query());
+    assert_eq!(iter.next().unwrap().unwrap().name, "a");
+    assert_eq!(iter.next().unwrap().unwrap().name, "b");
+    assert!(matches!(
+        iter.next(),
+        Some(Err(Error::Status { code: 500, .. }))
+    ));
+    assert!(iter.next().is_none());
+    assert_eq!(mock.urls(), vec![FIRST, SECOND]);
+}
+
+#[test]
+fn versions_follow_cursor_verbatim() {
+    let first = "http://registry.test/api/v1/crates/foo/versions?per_page=2&sort=semver";
+    let second = "http://registry.test/api/v1/crates/foo/versions?sort=semver&per_page=2&seek=Mi4w";
+    let mock = Arc::new(
+        Mock::new(500, "unexpected request")
+            .route(
+                first,
+                200,
+                &versions_page(
+                    &["3.0.0", "2.1.0"],
+                    3,
+                    Some("?sort=semver&per_page=2&seek=Mi4w"),
+                ),
+            )
+            .route(second, 200, &versions_page(&["1.0.0"], 3, None)),
+    );
+    let client = mirror_client(&mock);
+    let nums = client
+        .crate_versions_iter("foo", Some(VersionSorting::Semver), query())
+        .map(|v| v.unwrap().num)
+        .collect::<Vec<_>>();
+    assert_eq!(nums, vec!["3.0.0", "2.1.0", "1.0.0"]);
+    assert_eq!(mock.urls(), vec![first, second]);
+}
+
+#[test]
+fn versions_sorted_by_date() {
+    let mock = Arc::new(Mock::new(200, &versions_page(&["1.0.0"], 1, None)));
+    let versions = mirror_client(&mock)
+        .get_crate_versions("foo", Some(VersionSorting::Date), query())
+        .unwrap();
+    assert_eq!(versions.meta.prev_page, None);
+    assert_eq!(
+        mock.urls(),
+        vec!["http://registry.test/api/v1/crates/foo/versions?per_page=2&sort=date"]
+    );
+}
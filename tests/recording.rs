@@ -0,0 +1,86 @@
+//! Checks that recorded responses are served back unchanged by the replay
+//! transport.
+
+mod common;
+
+use std::fs;
+use std::sync::Arc;
+
+use common::{crates_page, Mock, MIRROR_URL, USER_AGENT};
+use consecrates::transport::{fixture_name, Request};
+use consecrates::{
+    Client, Error, RateLimiter, RecordingTransport, ReplayTransport, RetryPolicy, Timeouts,
+    Transport,
+};
+
+fn client(transport: impl Transport + 'static) -> Client {
+    Client::builder(USER_AGENT)
+        .base_url(MIRROR_URL)
+        .rate_limiter(RateLimiter::disabled())
+        .retry_policy(RetryPolicy::never())
+        .transport(transport)
+        .build()
+        .unwrap()
+}
+
+fn names(client: &Client) -> Vec<String> {
+    client
+        .get_crates(Default::default())
+        .unwrap()
+        .crates
+        .into_iter()
+        .map(|c| c.name)
+        .collect()
+}
+
+fn missing_detail(client: &Client) -> Option<String> {
+    match client.get_crate("missing") {
+        Err(Error::NotFound { detail, .. }) => detail,
+        other => panic!("expected not found, got {:?}", other),
+    }
+}
+
+#[test]
+fn recorded_responses_are_replayed() {
+    let dir = std::env::temp_dir().join(format!("consecrates-fixtures-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let missing_url = format!("{}crates/missing", MIRROR_URL);
+    let mock = Arc::new(
+        Mock::new(
+            404,
+            r#"{"errors":[{"detail":"crate `missing` does not exist"}]}"#,
+        )
+        .header("X-Request-Id", "abc")
+        .route(
+            &format!("{}crates?per_page=100", MIRROR_URL),
+            200,
+            &crates_page(&["a", "b"], 2, None),
+        ),
+    );
+
+    let recording = client(RecordingTransport::wrap(&dir, mock.clone()));
+    assert_eq!(names(&recording), vec!["a", "b"]);
+    let detail = missing_detail(&recording);
+    assert!(dir
+        .join(format!("{}.meta.json", fixture_name(&missing_url)))
+        .is_file());
+
+    let replaying = client(ReplayTransport::new(&dir));
+    assert_eq!(names(&replaying), vec!["a", "b"]);
+    assert_eq!(missing_detail(&replaying), detail);
+    assert_eq!(mock.urls().len(), 2);
+
+    let replayed = ReplayTransport::new(&dir)
+        .send(&Request {
+            url: missing_url,
+            headers: Vec::new(),
+            timeouts: Timeouts::default(),
+        })
+        .unwrap();
+    assert_eq!(replayed.status, 404);
+    assert_eq!(replayed.header("x-request-id"), Some("abc"));
+    assert!(String::from_utf8(replayed.body)
+        .unwrap()
+        .contains("does not exist"));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,77 @@
+//! Checks that `HttpReqTransport` keeps request settings across redirects,
+//! using a local plain http server.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use consecrates::transport::Request;
+use consecrates::{HttpReqTransport, Timeouts, Transport};
+
+/// Serves the given responses to consecutive connections, returning the
+/// received request heads.
+fn serve(listener: TcpListener, responses: Vec<String>) -> thread::JoinHandle<Vec<String>> {
+    thread::spawn(move || {
+        let mut heads = Vec::new();
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            heads.push(head);
+        }
+        heads
+    })
+}
+
+fn redirect(location: &str) -> String {
+    format!(
+        "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        location
+    )
+}
+
+#[test]
+fn redirects_keep_headers_but_not_authorization_across_hosts() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = serve(
+        listener,
+        vec![
+            redirect("/readme"),
+            redirect(&format!("http://localhost:{}/static/readme", port)),
+            "HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\nreadme".to_string(),
+        ],
+    );
+
+    let response = HttpReqTransport
+        .send(&Request {
+            url: format!("http://127.0.0.1:{}/api/v1/crates/serde/1.0.0/readme", port),
+            headers: vec![
+                ("User-Agent".to_string(), "tests (me@me.com)".to_string()),
+                ("Authorization".to_string(), "s3cr3t".to_string()),
+            ],
+            timeouts: Timeouts::default(),
+        })
+        .unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"readme");
+
+    let heads = server.join().unwrap();
+    assert!(heads[1].starts_with("GET /readme "));
+    assert!(heads[2].starts_with("GET /static/readme "));
+    for head in &heads {
+        assert!(head.contains("User-Agent: tests (me@me.com)"), "{}", head);
+    }
+    assert!(heads[0].contains("Authorization: s3cr3t"));
+    assert!(heads[1].contains("Authorization: s3cr3t"));
+    assert!(!heads[2].contains("s3cr3t"), "{}", heads[2]);
+}
@@ -0,0 +1,78 @@
+//! Checks that blocking calls retry transient failures and `try_get_*` calls
+//! don't.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use common::{Mock, EMPTY_CRATES, MIRROR_URL, USER_AGENT};
+use consecrates::{Client, Error, Query, RateLimiter, RetryPolicy};
+
+const CRATES: &str = "http://registry.test/api/v1/crates?per_page=100";
+
+fn retrying_client(mock: &Arc<Mock>, base_delay: Duration) -> Client {
+    Client::builder(USER_AGENT)
+        .base_url(MIRROR_URL)
+        .rate_limiter(RateLimiter::disabled())
+        .retry_policy(RetryPolicy::new(3).base_delay(base_delay).jitter(false))
+        .transport(mock.clone())
+        .build()
+        .unwrap()
+}
+
+fn flaky_mock() -> Arc<Mock> {
+    Arc::new(
+        Mock::new(404, "")
+            .route(CRATES, 503, "")
+            .route(CRATES, 503, "")
+            .route(CRATES, 200, EMPTY_CRATES),
+    )
+}
+
+#[test]
+fn blocking_call_retries_server_errors() {
+    let mock = flaky_mock();
+    let client = retrying_client(&mock, Duration::from_millis(1));
+    let crates = client.get_crates(Query::default()).unwrap();
+    assert!(crates.crates.is_empty());
+    assert_eq!(mock.urls(), vec![CRATES; 3]);
+}
+
+#[test]
+fn blocking_call_gives_up_after_max_attempts() {
+    let mock = Arc::new(Mock::new(404, "").route(CRATES, 503, ""));
+    let client = retrying_client(&mock, Duration::from_millis(1));
+    match client.get_crates(Query::default()) {
+        Err(Error::Status { code, .. }) => assert_eq!(code, 503),
+        other => panic!("expected status error, got {:?}", other),
+    }
+    assert_eq!(mock.requests().len(), 3);
+}
+
+#[test]
+fn try_call_fails_right_away() {
+    let mock = flaky_mock();
+    let client = retrying_client(&mock, Duration::from_millis(1));
+    match client.try_get_crates(Query::default()) {
+        Err(Error::Status { code, .. }) => assert_eq!(code, 503),
+        other => panic!("expected status error, got {:?}", other),
+    }
+    assert_eq!(mock.requests().len(), 1);
+}
+
+#[test]
+fn too_many_requests_waits_for_retry_after() {
+    let mock = Arc::new(
+        Mock::new(404, "")
+            .route(CRATES, 429, "")
+            .header("Retry-After", "0")
+            .route(CRATES, 200, EMPTY_CRATES),
+    );
+    // the computed delay would make the test hang, the header overrides it
+    let client = retrying_client(&mock, Duration::from_secs(60));
+    let start = Instant::now();
+    client.get_crates(Query::default()).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(mock.requests().len(), 2);
+}
@@ -0,0 +1,69 @@
+//! Checks how unsuccessful responses are turned into errors.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{mirror_client, Mock};
+use consecrates::Error;
+
+#[test]
+fn not_found_carries_detail() {
+    let mock = Arc::new(Mock::new(
+        404,
+        r#"{"errors":[{"detail":"crate `no-such-crate` does not exist"}]}"#,
+    ));
+    match mirror_client(&mock).get_crate("no-such-crate") {
+        Err(Error::NotFound { url, detail }) => {
+            assert_eq!(url, "http://registry.test/api/v1/crates/no-such-crate");
+            assert_eq!(
+                detail.as_deref(),
+                Some("crate `no-such-crate` does not exist")
+            );
+        }
+        other => panic!("expected not found, got {:?}", other),
+    }
+}
+
+#[test]
+fn error_payload_becomes_api_error() {
+    let mock = Arc::new(Mock::new(
+        403,
+        r#"{"errors":[{"detail":"must be logged in"},{"detail":"really"}]}"#,
+    ));
+    match mirror_client(&mock).get_crate("serde") {
+        Err(Error::Api { code, errors, .. }) => {
+            assert_eq!(code, 403);
+            assert_eq!(errors.len(), 2);
+            assert_eq!(errors[0].detail, "must be logged in");
+        }
+        other => panic!("expected api error, got {:?}", other),
+    }
+}
+
+#[test]
+fn non_json_server_error_becomes_status() {
+    let mock = Arc::new(Mock::new(502, "<html>Bad Gateway</html>"));
+    match mirror_client(&mock).get_crate("serde") {
+        Err(Error::Status { code, snippet, .. }) => {
+            assert_eq!(code, 502);
+            assert_eq!(snippet, "<html>Bad Gateway</html>");
+        }
+        other => panic!("expected status error, got {:?}", other),
+    }
+}
+
+#[test]
+fn error_payload_with_success_status() {
+    let mock = Arc::new(Mock::new(
+        200,
+        r#"{"errors":[{"detail":"invalid version"}]}"#,
+    ));
+    match mirror_client(&mock).get_crate_version("serde", "x") {
+        Err(Error::Api { code, errors, .. }) => {
+            assert_eq!(code, 200);
+            assert_eq!(errors[0].detail, "invalid version");
+        }
+        other => panic!("expected api error, got {:?}", other),
+    }
+}
@@ -0,0 +1,104 @@
+//! Checks the urls produced by the client for tricky query inputs.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{crates_page, mirror_client, Mock, EMPTY_CRATES, USER_AGENT};
+use consecrates::{Client, Error, Query};
+
+fn crates_url(query: Query) -> String {
+    let mock = Arc::new(Mock::new(200, EMPTY_CRATES));
+    Client::builder(USER_AGENT)
+        .transport(mock.clone())
+        .build()
+        .unwrap()
+        .get_crates(query)
+        .unwrap();
+    mock.urls()[0].clone()
+}
+
+#[test]
+fn no_page_has_no_dangling_separator() {
+    let url = crates_url(Query::default());
+    assert_eq!(url, "https://crates.io/api/v1/crates?per_page=100");
+}
+
+#[test]
+fn spaces_and_reserved_chars_are_encoded() {
+    let url = crates_url(Query {
+        string: Some("serde json&sort=new#frag".to_string()),
+        per_page: None,
+        ..Default::default()
+    });
+    assert_eq!(
+        url,
+        "https://crates.io/api/v1/crates?q=serde%20json%26sort%3Dnew%23frag"
+    );
+}
+
+#[test]
+fn non_ascii_is_encoded_as_utf8() {
+    let url = crates_url(Query {
+        string: Some("zażółć".to_string()),
+        keywords: vec!["日本".to_string()],
+        page: Some(2),
+        per_page: None,
+        ..Default::default()
+    });
+    assert_eq!(
+        url,
+        "https://crates.io/api/v1/crates?page=2&q=za%C5%BC%C3%B3%C5%82%C4%87&keyword=%E6%97%A5%E6%9C%AC"
+    );
+}
+
+#[test]
+fn browse_filters_are_serialized() {
+    let url = crates_url(Query::from_str(
+        "letter=s kw=async kw=http yanked=no following=yes",
+    ));
+    assert_eq!(
+        url,
+        "https://crates.io/api/v1/crates?per_page=100&letter=s&all_keywords=async%20http&include_yanked=no&following=1"
+    );
+}
+
+#[test]
+fn only_first_category_is_sent() {
+    let url = crates_url(Query::from_str("cat=async cat=net cat=async kw=tokio"));
+    assert_eq!(
+        url,
+        "https://crates.io/api/v1/crates?per_page=100&category=asynchronous&keyword=tokio"
+    );
+}
+
+#[test]
+fn extra_categories_filter_the_page() {
+    let mock = Arc::new(
+        Mock::new(500, "unexpected request")
+            .route(
+                "http://registry.test/api/v1/crates?per_page=100&category=asynchronous",
+                200,
+                &crates_page(&["a", "b", "c"], 3, None),
+            )
+            .route(
+                "http://registry.test/api/v1/crates?ids%5B%5D=a&ids%5B%5D=b&ids%5B%5D=c\
+                 &category=network-programming&per_page=3",
+                200,
+                &crates_page(&["b"], 1, None),
+            ),
+    );
+    let client = mirror_client(&mock);
+    let crates = client
+        .get_crates(Query::from_str("cat=async cat=net"))
+        .unwrap();
+    let names = crates.crates.iter().map(|c| &c.name).collect::<Vec<_>>();
+    assert_eq!(names, vec!["b"]);
+    assert_eq!(mock.urls().len(), 2);
+
+    assert!(matches!(
+        client.try_get_crates(Query::from_str("cat=async cat=net")),
+        Err(Error::InvalidQuery(_))
+    ));
+    assert_eq!(mock.urls().len(), 2);
+}